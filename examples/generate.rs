@@ -0,0 +1,32 @@
+//! Renders a seeded banner without the server.
+//!
+//! `cargo run --example generate -- <seed> [out.png]`, the seed can be pinned to a version like `v1-42`, run from a directory with a `patterns` folder.
+
+use anyhow::{Context, Result};
+use minecraft_banners::{
+    BannerGenerator, GenerateOptions, GeneratorVersion, PatternRegistry, parse_seed,
+};
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let seed = args.next().as_deref().and_then(parse_seed);
+    let out = args.next().unwrap_or("banner.png".to_string());
+
+    let registry = PatternRegistry::load("patterns").context("Failed to load patterns")?;
+    // plain seeds use the latest version, `v1-42` pins one
+    let version = seed
+        .and_then(|s| s.version)
+        .unwrap_or(GeneratorVersion::LATEST);
+    let generator = BannerGenerator::with_version(&registry, version);
+    let (seed, banner) = generator.generate(seed.map(|s| s.seed), GenerateOptions::default())?;
+
+    println!("seed {version}-{seed}, base {}", banner.base);
+    for (pattern_id, color) in &banner.layers {
+        println!("  {} {color}", registry.name(*pattern_id).unwrap());
+    }
+
+    registry.render(&banner)?.save(&out)?;
+    println!("saved to {out}");
+
+    Ok(())
+}
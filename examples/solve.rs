@@ -0,0 +1,33 @@
+//! Approximates an image with a banner without the server.
+//!
+//! `cargo run -r --example solve -- <image> [out.png]`, run from a directory with a `patterns` folder.
+
+use anyhow::{Context, Result};
+use minecraft_banners::{PatternRegistry, SolveOptions, Solver};
+use std::time::Instant;
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let input = args.next().context("Missing the image to solve")?;
+    let out = args.next().unwrap_or("banner.png".to_string());
+
+    let registry = PatternRegistry::load("patterns").context("Failed to load patterns")?;
+    let target = image::open(&input)?.to_rgba8();
+
+    let start = Instant::now();
+    let solution = Solver::new(&registry)?.solve(&target, SolveOptions::default());
+    println!(
+        "error {:.2} in {:?}, base {}",
+        solution.error,
+        start.elapsed(),
+        solution.banner.base
+    );
+    for (pattern_id, color) in &solution.banner.layers {
+        println!("  {} {color}", registry.name(*pattern_id).unwrap());
+    }
+
+    registry.render(&solution.banner)?.save(&out)?;
+    println!("saved to {out}");
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! Finding the layers of a banner that don't change how it looks.
+//!
+//! Lots of layer lists render the exact same banner, a pattern fully covered by later ones
+//! or one dyed the same color as whatever is beneath it can just be left out.
+
+use crate::{Banner, Color, Error, Image, Pattern, as_raw, composite, tint_image};
+
+/// The indices of the layers that actually show up, in order.
+///
+/// A layer is left out when it doesn't change a single pixel where it sits in the stack
+/// (white on a white base), or when the fully opaque pixels of later layers cover every pixel
+/// its alpha mask touches. Rendering only the kept layers is pixel-identical to rendering all of them.
+pub fn canonical_layers(
+    base: &Image,
+    base_color: Color,
+    layers: &[(&Pattern, Color)],
+) -> Result<Vec<usize>, Error> {
+    let base = Banner::new(&mut base.clone(), base_color)?.img_owned();
+    let tinted = layers
+        .iter()
+        .map(|(pattern, color)| {
+            if pattern.dimensions() != (Banner::X, Banner::Y) {
+                return Err(Error::InvalidBannerDimension);
+            }
+            let mut pattern = pattern.img().clone();
+            tint_image(&mut pattern, color)?;
+            Ok(pattern)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // dropping a layer can turn another one into a no-op, so keep going until nothing changes
+    let mut kept = (0..layers.len()).collect::<Vec<_>>();
+    loop {
+        let before = kept.len();
+        kept = without_no_ops(&base, &tinted, &kept);
+        kept = without_occluded(&tinted, &kept);
+        if kept.len() == before {
+            return Ok(kept);
+        }
+    }
+}
+
+/// Drops every layer that leaves the banner exactly as it was underneath
+fn without_no_ops(base: &Image, tinted: &[Image], kept: &[usize]) -> Vec<usize> {
+    let mut banner = *as_raw(base);
+    let mut visible = Vec::with_capacity(kept.len());
+
+    for &i in kept {
+        let mut next = banner;
+        composite::overlay(&mut next, as_raw(&tinted[i]));
+        if next != banner {
+            banner = next;
+            visible.push(i);
+        }
+    }
+
+    visible
+}
+
+/// Drops every layer whose alpha mask is fully covered by opaque pixels of the layers above it
+fn without_occluded(tinted: &[Image], kept: &[usize]) -> Vec<usize> {
+    let mut covered = [false; composite::PIXELS];
+    let mut visible = Vec::with_capacity(kept.len());
+
+    for &i in kept.iter().rev() {
+        let alpha = tinted[i].pixels().map(|p| p[3]);
+        let occluded = alpha
+            .clone()
+            .zip(covered)
+            .all(|(alpha, covered)| alpha == 0 || covered);
+        if occluded {
+            continue;
+        }
+
+        for (covered, alpha) in covered.iter_mut().zip(alpha) {
+            *covered |= alpha == 255;
+        }
+        visible.push(i);
+    }
+
+    visible.reverse();
+    visible
+}
@@ -58,6 +58,42 @@ impl Color {
     pub const fn all() -> &'static [Self] {
         Color::VARIANTS
     }
+
+    /// Returns the **RGB** values of the [`Color`]
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        // every variant has a valid hex code
+        hex_to_rgb(&self.to_string()).unwrap()
+    }
+
+    /// Returns the dye's item name, like `light_blue`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Color::White => "white",
+            Color::Orange => "orange",
+            Color::Magenta => "magenta",
+            Color::LightBlue => "light_blue",
+            Color::Yellow => "yellow",
+            Color::Lime => "lime",
+            Color::Pink => "pink",
+            Color::Gray => "gray",
+            Color::LightGray => "light_gray",
+            Color::Cyan => "cyan",
+            Color::Purple => "purple",
+            Color::Blue => "blue",
+            Color::Brown => "brown",
+            Color::Green => "green",
+            Color::Red => "red",
+            Color::Black => "black",
+        }
+    }
+
+    /// Finds a [`Color`] by its dye's item name (see [`Color::name`]), ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Color::all()
+            .iter()
+            .find(|c| c.name().eq_ignore_ascii_case(name))
+            .copied()
+    }
 }
 
 /// Simply converts a `&str` of **Hex** color into **RGB** (u8, u8, u8).  
@@ -0,0 +1,189 @@
+//! Fixed size `20x40` RGBA compositing.
+//!
+//! Every [`Banner`](crate::Banner) and [`Pattern`](crate::Pattern) is exactly the same size,
+//! so instead of going through the generic per-pixel float math in [`image`]
+//! this works directly on the raw bytes with integer multiply-divide.
+//!
+//! The output is byte-identical to the old `f32` tint + [`image::imageops::overlay`] path.
+//! Whenever the exact integer result sits right on a rounding boundary (where the float path
+//! could land on either side) the pixel falls back to [`image::Pixel::blend`].
+//!
+//! With the `simd` feature (nightly only) blending uses [`std::simd`],
+//! otherwise a scalar loop is used.
+
+use image::{Pixel, Rgba};
+use std::sync::LazyLock;
+
+use crate::Banner;
+
+/// How many pixels a [`Banner`] has
+pub const PIXELS: usize = (Banner::X * Banner::Y) as usize;
+/// How many bytes a raw RGBA [`Banner`] buffer takes up
+pub const BYTES: usize = PIXELS * 4;
+
+/// The resulting alpha after blending `[bottom][top]` alpha values.
+///
+/// The float blend truncates `255 * alpha` which isn't always the exact integer,
+/// so it's easier to just compute them all once with the real thing.
+static ALPHA: LazyLock<Box<[[u8; 256]; 256]>> = LazyLock::new(|| {
+    let mut table = Box::new([[0u8; 256]; 256]);
+    for (bottom, row) in table.iter_mut().enumerate() {
+        for (top, out) in row.iter_mut().enumerate() {
+            let mut pixel = Rgba([0, 0, 0, bottom as u8]);
+            pixel.blend(&Rgba([0, 0, 0, top as u8]));
+            *out = pixel[3];
+        }
+    }
+    table
+});
+
+/// Exact `x / 255` for any `x <= 255 * 255`
+#[inline(always)]
+fn div255(x: u32) -> u32 {
+    (x + 1 + (x >> 8)) >> 8
+}
+
+/// Tints raw RGBA bytes with the given RGB color.
+///
+/// Same as `(current.r as f32 * color.r as f32 / 255.0f32) as u8` but without the floats.
+/// Works on any amount of pixels, not just a [`Banner`] sized one.
+pub fn tint(data: &mut [u8], (red, green, blue): (u8, u8, u8)) {
+    let (red, green, blue) = (red as u32, green as u32, blue as u32);
+
+    for pixel in data.chunks_exact_mut(4) {
+        pixel[0] = div255(pixel[0] as u32 * red) as u8;
+        pixel[1] = div255(pixel[1] as u32 * green) as u8;
+        pixel[2] = div255(pixel[2] as u32 * blue) as u8;
+    }
+}
+
+/// Overlays `top` onto `bottom` using source-over alpha compositing.
+pub fn overlay(bottom: &mut [u8; BYTES], top: &[u8; BYTES]) {
+    #[cfg(feature = "simd")]
+    simd::overlay(bottom, top);
+    #[cfg(not(feature = "simd"))]
+    scalar::overlay(bottom, top);
+}
+
+/// Blends a single pixel, falling back to the float blend on rounding boundaries.
+#[inline(always)]
+fn blend_pixel(bottom: &mut [u8], top: &[u8]) {
+    let fg_a = top[3] as u32;
+    if fg_a == 0 {
+        return;
+    }
+    if fg_a == 255 {
+        bottom.copy_from_slice(top);
+        return;
+    }
+
+    let bg_a = bottom[3] as u32;
+    if bg_a == 255 {
+        // the common case, the banner base is fully opaque
+        // so the exact result is (fg * fg_a + bg * (255 - fg_a)) / 255
+        let mut out = [0u8; 3];
+        for c in 0..3 {
+            let x = top[c] as u32 * fg_a + bottom[c] as u32 * (255 - fg_a);
+            let q = div255(x);
+            if q * 255 == x {
+                return blend_float(bottom, top);
+            }
+            out[c] = q as u8;
+        }
+        bottom[..3].copy_from_slice(&out);
+        bottom[3] = ALPHA[255][fg_a as usize];
+        return;
+    }
+
+    // exact result is n / d where d is the final alpha (scaled by 255 * 255)
+    let d = 255 * (fg_a + bg_a) - fg_a * bg_a;
+    let mut out = [0u8; 3];
+    for c in 0..3 {
+        let n = top[c] as u32 * fg_a * 255 + bottom[c] as u32 * bg_a * (255 - fg_a);
+        let (q, r) = (n / d, n % d);
+        // the float path is only ever off by a tiny fraction,
+        // so anything not within 1/1024 of a whole number truncates the same
+        if r * 1024 < d || (d - r) * 1024 < d {
+            return blend_float(bottom, top);
+        }
+        out[c] = q as u8;
+    }
+    bottom[..3].copy_from_slice(&out);
+    bottom[3] = ALPHA[bg_a as usize][fg_a as usize];
+}
+
+#[cold]
+fn blend_float(bottom: &mut [u8], top: &[u8]) {
+    let mut pixel = Rgba([bottom[0], bottom[1], bottom[2], bottom[3]]);
+    pixel.blend(&Rgba([top[0], top[1], top[2], top[3]]));
+    bottom.copy_from_slice(&pixel.0);
+}
+
+#[cfg(not(feature = "simd"))]
+mod scalar {
+    use super::{BYTES, blend_pixel};
+
+    pub fn overlay(bottom: &mut [u8; BYTES], top: &[u8; BYTES]) {
+        for (bottom, top) in bottom.chunks_exact_mut(4).zip(top.chunks_exact(4)) {
+            blend_pixel(bottom, top);
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+mod simd {
+    use super::{ALPHA, BYTES, blend_pixel};
+    use std::simd::prelude::*;
+
+    /// 8 RGBA pixels per lane group
+    const LANES: usize = 32;
+
+    const ALPHA_INDEX: [usize; LANES] = {
+        let mut index = [0; LANES];
+        let mut i = 0;
+        while i < LANES {
+            index[i] = i - i % 4 + 3;
+            i += 1;
+        }
+        index
+    };
+
+    pub fn overlay(bottom: &mut [u8; BYTES], top: &[u8; BYTES]) {
+        let color_lanes =
+            u16x32::from_array(std::array::from_fn(|i| (i % 4) as u16)).simd_ne(u16x32::splat(3));
+
+        for (bottom, top) in bottom.chunks_exact_mut(LANES).zip(top.chunks_exact(LANES)) {
+            let bg = u8x32::from_slice(bottom);
+            let fg = u8x32::from_slice(top);
+
+            let bg_a: u8x32 = simd_swizzle!(bg, ALPHA_INDEX);
+            if bg_a.simd_ne(u8x32::splat(255)).any() {
+                // not fully opaque, rare enough to not bother
+                for (bottom, top) in bottom.chunks_exact_mut(4).zip(top.chunks_exact(4)) {
+                    blend_pixel(bottom, top);
+                }
+                continue;
+            }
+
+            let fg_a: u16x32 = simd_swizzle!(fg, ALPHA_INDEX).cast();
+            let x = fg.cast::<u16>() * fg_a + bg.cast::<u16>() * (u16x32::splat(255) - fg_a);
+            // same as div255, x + 1 + (x >> 8) still fits in a u16
+            let q = (x + u16x32::splat(1) + (x >> 8)) >> 8;
+
+            // a whole number result could truncate either way in the float path
+            let partial = fg_a.simd_ne(u16x32::splat(0)) & fg_a.simd_ne(u16x32::splat(255));
+            let boundary = (q * u16x32::splat(255)).simd_eq(x) & partial & color_lanes;
+            if boundary.any() {
+                for (bottom, top) in bottom.chunks_exact_mut(4).zip(top.chunks_exact(4)) {
+                    blend_pixel(bottom, top);
+                }
+                continue;
+            }
+
+            q.cast::<u8>().copy_to_slice(bottom);
+            for (bottom, top) in bottom.chunks_exact_mut(4).zip(top.chunks_exact(4)) {
+                bottom[3] = ALPHA[255][top[3] as usize];
+            }
+        }
+    }
+}
@@ -1,8 +1,12 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
 use image::{ImageBuffer, Rgba};
 use std::ops::Deref;
 use thiserror::Error;
 
+pub mod canonical;
 mod color;
+pub mod composite;
 pub use color::Color;
 
 #[derive(Debug, Error)]
@@ -41,7 +45,12 @@ impl Banner {
         let mut banner = Image::new(Banner::X, Banner::Y);
 
         tint_image(base, &color)?;
-        image::imageops::overlay(&mut banner, base, 0, 0);
+        if base.dimensions() == (Banner::X, Banner::Y) {
+            composite::overlay(as_raw_mut(&mut banner), as_raw(base));
+        } else {
+            // custom base textures can be any size, those just get cropped
+            image::imageops::overlay(&mut banner, base, 0, 0);
+        }
 
         Ok(Banner { image: banner })
     }
@@ -67,7 +76,7 @@ impl Banner {
 
         let mut pattern = pattern.img_owned();
         tint_image(&mut pattern, color)?;
-        image::imageops::overlay(&mut self.image, &pattern, 0, 0);
+        composite::overlay(as_raw_mut(&mut self.image), as_raw(&pattern));
 
         Ok(())
     }
@@ -79,6 +88,7 @@ impl Banner {
 }
 
 /// A Banner Pattern
+#[derive(Debug, Clone)]
 pub struct Pattern {
     image: Image,
 }
@@ -125,13 +135,20 @@ impl Deref for Banner {
 ///
 /// `(current.r as f32 * color.r as f32 / 255.0f32) as u8`
 fn tint_image(data: &mut Image, color: &Color) -> Result<(), Error> {
-    let (red, green, blue) = color::hex_to_rgb(&color.to_string())?;
-
-    for pixel in data.pixels_mut() {
-        pixel[0] = (pixel[0] as f32 * red as f32 / 255.0) as u8;
-        pixel[1] = (pixel[1] as f32 * green as f32 / 255.0) as u8;
-        pixel[2] = (pixel[2] as f32 * blue as f32 / 255.0) as u8;
-    }
+    let rgb = color::hex_to_rgb(&color.to_string())?;
+    composite::tint(data, rgb);
 
     Ok(())
 }
+
+/// The raw bytes of a [`Banner`] sized [`Image`].
+///
+/// Panics if the image isn't `20x40`, which is always checked beforehand.
+fn as_raw(image: &Image) -> &[u8; composite::BYTES] {
+    image.as_raw().as_slice().try_into().unwrap()
+}
+
+/// Mutable version of [`as_raw`]
+fn as_raw_mut(image: &mut Image) -> &mut [u8; composite::BYTES] {
+    (&mut **image).try_into().unwrap()
+}
@@ -0,0 +1,141 @@
+use image::{ImageBuffer, Rgba};
+use mcb::{Banner, Color, Pattern, canonical::canonical_layers};
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// A full size `64x64` texture, `alpha` gets the position on the banner front
+fn texture(alpha: impl Fn(u32, u32) -> u8) -> Pattern {
+    Pattern::new(Image::from_fn(64, 64, |x, y| {
+        let (x, y) = (x.wrapping_sub(1), y.wrapping_sub(1));
+        if x < Banner::X && y < Banner::Y {
+            Rgba([255, 255, 255, alpha(x, y)])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    }))
+}
+
+fn banner(base: &Image, base_color: Color, layers: &[(&Pattern, Color)]) -> Image {
+    let mut banner = Banner::new(&mut base.clone(), base_color).unwrap();
+    for (pattern, color) in layers {
+        banner.add_pattern((*pattern).clone(), color).unwrap();
+    }
+    banner.img_owned()
+}
+
+struct Patterns {
+    full: Pattern,
+    left: Pattern,
+    top: Pattern,
+    faded: Pattern,
+    empty: Pattern,
+}
+
+fn patterns() -> Patterns {
+    Patterns {
+        full: texture(|_, _| 255),
+        left: texture(|x, _| if x < 10 { 255 } else { 0 }),
+        top: texture(|_, y| if y < 20 { 255 } else { 0 }),
+        faded: texture(|x, y| ((x * 7 + y * 3) % 256) as u8),
+        empty: texture(|_, _| 0),
+    }
+}
+
+#[test]
+fn drops_covered_layers() {
+    let base = Banner::load_base().unwrap();
+    let p = patterns();
+
+    let layers = [
+        (&p.left, Color::Red),
+        (&p.faded, Color::Blue),
+        (&p.full, Color::Green),
+        (&p.top, Color::Black),
+    ];
+    assert_eq!(
+        canonical_layers(&base, Color::White, &layers).unwrap(),
+        vec![2, 3]
+    );
+}
+
+#[test]
+fn drops_no_ops() {
+    let base = Banner::load_base().unwrap();
+    let p = patterns();
+
+    // patterns have their own texture, so only the same pattern twice is a no-op
+    let layers = [
+        (&p.left, Color::Red),
+        (&p.empty, Color::Black),
+        (&p.left, Color::Red),
+        (&p.top, Color::Blue),
+        (&p.top, Color::Blue),
+    ];
+    assert_eq!(
+        canonical_layers(&base, Color::White, &layers).unwrap(),
+        vec![0, 3]
+    );
+}
+
+#[test]
+fn keeps_layers_that_show() {
+    let base = Banner::load_base().unwrap();
+    let p = patterns();
+
+    // the faded one is never fully covered, the left one shows below the top
+    let layers = [
+        (&p.faded, Color::Red),
+        (&p.left, Color::Blue),
+        (&p.top, Color::Black),
+    ];
+    assert_eq!(
+        canonical_layers(&base, Color::White, &layers).unwrap(),
+        vec![0, 1, 2]
+    );
+}
+
+/// Tiny deterministic generator so the stacks are always the same
+struct Lcg(u64);
+impl Lcg {
+    fn next(&mut self) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 56) as usize
+    }
+}
+
+#[test]
+fn renders_the_same() {
+    let base = Banner::load_base().unwrap();
+    let p = patterns();
+    let all = [&p.full, &p.left, &p.top, &p.faded, &p.empty];
+    // few colors so no-ops actually happen
+    let colors = [Color::White, Color::Black, Color::Red];
+    let mut rng = Lcg(7);
+
+    for _ in 0..500 {
+        let base_color = colors[rng.next() % colors.len()];
+        let layers = (0..Banner::VANILLA_MAX_PATTERN_SIZE)
+            .map(|_| {
+                (
+                    all[rng.next() % all.len()],
+                    colors[rng.next() % colors.len()],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let kept = canonical_layers(&base, base_color, &layers).unwrap();
+        let canonical = kept.iter().map(|i| layers[*i]).collect::<Vec<_>>();
+        assert_eq!(
+            banner(&base, base_color, &canonical),
+            banner(&base, base_color, &layers),
+            "{kept:?}"
+        );
+
+        // already minimal
+        let again = canonical_layers(&base, base_color, &canonical).unwrap();
+        assert_eq!(again, (0..canonical.len()).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,195 @@
+use image::{ImageBuffer, ImageReader, Rgba};
+use mcb::{Banner, Color, Pattern, composite};
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// The original `f32` tint that the integer one has to match
+fn legacy_tint(data: &mut Image, color: &Color) {
+    let (red, green, blue) = color.rgb();
+    for pixel in data.pixels_mut() {
+        pixel[0] = (pixel[0] as f32 * red as f32 / 255.0) as u8;
+        pixel[1] = (pixel[1] as f32 * green as f32 / 255.0) as u8;
+        pixel[2] = (pixel[2] as f32 * blue as f32 / 255.0) as u8;
+    }
+}
+
+/// The original banner pipeline, tint + [`image::imageops::overlay`]
+fn legacy_banner(base: &Image, base_color: Color, layers: &[(&Image, Color)]) -> Image {
+    let mut banner = Image::new(Banner::X, Banner::Y);
+    let mut base = base.clone();
+    legacy_tint(&mut base, &base_color);
+    image::imageops::overlay(&mut banner, &base, 0, 0);
+
+    for (pattern, color) in layers {
+        let mut pattern = Pattern::new((*pattern).clone()).img_owned();
+        legacy_tint(&mut pattern, color);
+        image::imageops::overlay(&mut banner, &pattern, 0, 0);
+    }
+
+    banner
+}
+
+fn banner(base: &Image, base_color: Color, layers: &[(&Image, Color)]) -> Image {
+    let mut banner = Banner::new(&mut base.clone(), base_color).unwrap();
+    for (pattern, color) in layers {
+        banner
+            .add_pattern(Pattern::new((*pattern).clone()), color)
+            .unwrap();
+    }
+    banner.img_owned()
+}
+
+/// Tiny deterministic generator so the synthetic patterns are always the same
+struct Lcg(u64);
+impl Lcg {
+    fn next(&mut self) -> u8 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 56) as u8
+    }
+}
+
+/// Every pattern in `/patterns` plus a few synthetic ones that hit every alpha value.
+fn patterns() -> Vec<(String, Image)> {
+    let mut patterns = Vec::new();
+
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../patterns");
+    if let Ok(files) = std::fs::read_dir(dir) {
+        for file in files {
+            let path = file.unwrap().path();
+            if path.extension().is_some_and(|e| e == "png") {
+                let img = ImageReader::open(&path).unwrap().decode().unwrap();
+                patterns.push((path.display().to_string(), img.to_rgba8()));
+            }
+        }
+    }
+
+    let gradient = Image::from_fn(64, 64, |x, y| {
+        Rgba([255, 255, 255, ((y * 64 + x) % 256) as u8])
+    });
+    patterns.push(("synthetic gradient".to_string(), gradient));
+
+    for seed in 0..4 {
+        let mut rng = Lcg(seed);
+        let noise = Image::from_fn(64, 64, |_, _| {
+            Rgba([rng.next(), rng.next(), rng.next(), rng.next()])
+        });
+        patterns.push((format!("synthetic noise {seed}"), noise));
+    }
+
+    patterns
+}
+
+#[test]
+fn tint_matches_float() {
+    for color in Color::all() {
+        let mut data = Image::from_fn(256, 1, |x, _| Rgba([x as u8, x as u8, x as u8, 255]));
+        let mut expected = data.clone();
+
+        composite::tint(&mut data, color.rgb());
+        legacy_tint(&mut expected, color);
+        assert_eq!(data, expected, "{color:?}");
+    }
+
+    // every possible channel * tint value
+    for value in 0..=255u8 {
+        let mut data = Image::from_fn(256, 1, |x, _| Rgba([x as u8, x as u8, x as u8, 255]));
+        composite::tint(&mut data, (value, value, value));
+        for (x, pixel) in data.pixels().enumerate() {
+            assert_eq!(pixel[0], (x as f32 * value as f32 / 255.0) as u8);
+        }
+    }
+}
+
+#[test]
+fn overlay_matches_float_blend() {
+    let mut rng = Lcg(0xBA22E2);
+    let mut bottom_alphas = vec![255, 254, 0, 1, 128];
+    bottom_alphas.extend((0..16).map(|_| rng.next()));
+
+    for bottom_alpha in bottom_alphas {
+        // every top alpha, top channel & bottom channel combination (opaque is the hot path)
+        // for the others just step through them
+        let step = if bottom_alpha == 255 { 1 } else { 7 };
+
+        for top_alpha in 0..=255u8 {
+            let mut pairs = Vec::new();
+            for fg in (0..=255u8).step_by(step) {
+                for bg in (0..=255u8).step_by(step) {
+                    pairs.push((fg, bg));
+                }
+            }
+
+            for chunk in pairs.chunks(composite::PIXELS) {
+                let mut bottom = [0u8; composite::BYTES];
+                let mut top = [0u8; composite::BYTES];
+                for (i, (fg, bg)) in chunk.iter().enumerate() {
+                    bottom[i * 4..i * 4 + 4].copy_from_slice(&[
+                        *bg,
+                        bg / 2,
+                        255 - bg,
+                        bottom_alpha,
+                    ]);
+                    top[i * 4..i * 4 + 4].copy_from_slice(&[*fg, 255 - fg, fg / 3, top_alpha]);
+                }
+
+                let mut expected = Image::from_raw(Banner::X, Banner::Y, bottom.to_vec()).unwrap();
+                let top_img = Image::from_raw(Banner::X, Banner::Y, top.to_vec()).unwrap();
+                image::imageops::overlay(&mut expected, &top_img, 0, 0);
+
+                composite::overlay(&mut bottom, &top);
+                assert_eq!(
+                    &bottom[..],
+                    expected.as_raw().as_slice(),
+                    "bottom alpha {bottom_alpha}, top alpha {top_alpha}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn golden_every_pattern_and_color() {
+    let base = Banner::load_base().unwrap();
+    let patterns = patterns();
+
+    for base_color in Color::all() {
+        for (name, pattern) in &patterns {
+            for color in Color::all() {
+                let layers = [(pattern, *color)];
+                assert_eq!(
+                    banner(&base, *base_color, &layers),
+                    legacy_banner(&base, *base_color, &layers),
+                    "{name} {color:?} on {base_color:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn golden_stacked_layers() {
+    let base = Banner::load_base().unwrap();
+    let patterns = patterns();
+    let mut rng = Lcg(42);
+
+    for _ in 0..500 {
+        let base_color = Color::all()[rng.next() as usize % Color::all().len()];
+        let layers = (0..Banner::VANILLA_MAX_PATTERN_SIZE)
+            .map(|_| {
+                let (_, pattern) = &patterns[rng.next() as usize % patterns.len()];
+                (
+                    pattern,
+                    Color::all()[rng.next() as usize % Color::all().len()],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            banner(&base, base_color, &layers),
+            legacy_banner(&base, base_color, &layers)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
-use mcb::{Banner, Color, Pattern};
+use mcb::{Banner, Color};
+use minecraft_banners::{BannerDescription, Combinations, GenerationError};
 use rand::{Rng, SeedableRng};
 
 use crate::AppState;
@@ -63,37 +64,40 @@ impl std::fmt::Debug for BannerId {
     }
 }
 
-// i honeslty have no clue if these are 100% correct (compute_layer_counts & compute_cumulative_counts mostly)
-fn compute_layer_counts(pattern_len: usize) -> [u64; Banner::VANILLA_MAX_PATTERN_SIZE + 1] {
-    let mut counts = [0u64; Banner::VANILLA_MAX_PATTERN_SIZE + 1];
-    let mut power: u64 = 1;
-    let base = (pattern_len * Color::all().len()) as u64;
-    for i in 0..=6 {
-        counts[i] = power;
-        if i < 6 {
-            power *= base;
-        }
+// layers only, the base color is picked separately
+fn compute_layer_counts(
+    pattern_len: usize,
+) -> Result<[u128; Banner::VANILLA_MAX_PATTERN_SIZE + 1], GenerationError> {
+    let per_layer = Combinations::vanilla(pattern_len)?.per_layer;
+    let colors = Color::all().len() as u128;
+
+    let mut counts = [0u128; Banner::VANILLA_MAX_PATTERN_SIZE + 1];
+    for (count, total) in counts.iter_mut().zip(per_layer) {
+        *count = total / colors;
     }
 
-    counts
+    Ok(counts)
 }
 fn compute_cumulative_counts(
-    counts: &[u64; Banner::VANILLA_MAX_PATTERN_SIZE + 1],
-) -> [u64; Banner::VANILLA_MAX_PATTERN_SIZE + 1] {
-    let mut cumulative = [0u64; Banner::VANILLA_MAX_PATTERN_SIZE + 1];
-    let mut sum = 0u64;
-    for i in 0..=6 {
-        cumulative[i] = sum;
-        sum += counts[i];
+    counts: &[u128; Banner::VANILLA_MAX_PATTERN_SIZE + 1],
+) -> [u128; Banner::VANILLA_MAX_PATTERN_SIZE + 1] {
+    let mut cumulative = [0u128; Banner::VANILLA_MAX_PATTERN_SIZE + 1];
+    let mut sum = 0u128;
+    for (total, count) in cumulative.iter_mut().zip(counts) {
+        *total = sum;
+        sum += count;
     }
     cumulative
 }
 
-fn generate_random_banner<R: rand::RngCore>(rng: &mut R, pattern_len: usize) -> BannerId {
+fn generate_random_banner<R: rand::RngCore>(
+    rng: &mut R,
+    pattern_len: usize,
+) -> Result<BannerId, GenerationError> {
     let mut banner = BannerId::new();
     banner.set_base_color(rng.random_range(0..Color::all().len()) as u8);
 
-    let counts = compute_layer_counts(pattern_len);
+    let counts = compute_layer_counts(pattern_len)?;
     let cumulative = compute_cumulative_counts(&counts);
     let total_banners =
         cumulative[Banner::VANILLA_MAX_PATTERN_SIZE] + counts[Banner::VANILLA_MAX_PATTERN_SIZE];
@@ -109,24 +113,25 @@ fn generate_random_banner<R: rand::RngCore>(rng: &mut R, pattern_len: usize) ->
         }
     }
 
+    let layer_combos = (pattern_len * Color::all().len()) as u128;
     for i in 0..num_layers {
-        let digit = index % (pattern_len * Color::all().len()) as u64;
-        index /= (pattern_len * Color::all().len()) as u64;
+        let digit = index % layer_combos;
+        index /= layer_combos;
 
-        let pattern_id = (digit / Color::all().len() as u64) as u8;
-        let color = (digit % Color::all().len() as u64) as u8;
+        let pattern_id = (digit / Color::all().len() as u128) as u8;
+        let color = (digit % Color::all().len() as u128) as u8;
 
         banner.set_layer(i as u8, pattern_id, color);
     }
 
-    banner
+    Ok(banner)
 }
 
 pub fn test(state: AppState) -> anyhow::Result<()> {
     let mut rng = rand_xorshift::XorShiftRng::from_os_rng();
 
     let time = std::time::Instant::now();
-    let banner = generate_random_banner(&mut rng, state.patterns.len());
+    let banner = generate_random_banner(&mut rng, state.registry.len())?;
     println!(
         "{:?}: {banner:?} ({}, {:b})",
         time.elapsed(),
@@ -134,17 +139,16 @@ pub fn test(state: AppState) -> anyhow::Result<()> {
         banner.0
     );
 
-    let mut banner_img = Banner::new(
-        &mut state.base.clone(),
-        Color::from_repr(banner.get_base_color()).unwrap(),
-    )?;
-
-    for i in 0..6 {
-        let (pattern, color) = banner.get_layer(i);
-        let pattern = Pattern::new(state.patterns[pattern as usize].1.clone());
-        banner_img.add_pattern(pattern, &Color::from_repr(color).unwrap())?;
-    }
-    banner_img.save("banner.png")?;
+    let banner = BannerDescription {
+        base: Color::from_repr(banner.get_base_color()).unwrap(),
+        layers: (0..6)
+            .map(|i| {
+                let (pattern, color) = banner.get_layer(i);
+                (pattern as usize, Color::from_repr(color).unwrap())
+            })
+            .collect(),
+    };
+    state.registry.render(&banner)?.save("banner.png")?;
 
     Ok(())
 }
@@ -0,0 +1,240 @@
+//! Banners written out in heraldry, like `Lime, a creeper charge sable, a bordure gules`.
+//!
+//! The field (base color) comes first, then every layer bottom to top as `a <charge> <tincture>`.
+//! Charges use the same names as the client's `asset_resolver.ts`, the dyes heraldry has a
+//! tincture for use it (`gules`, `azure`...) & the rest keep their dye name.
+
+use mcb::Color;
+use thiserror::Error;
+
+use crate::{BannerDescription, GenerationError, PatternRegistry};
+
+/// Every pattern & its heraldic name, the same as the client shows
+#[rustfmt::skip]
+pub(crate) const CHARGES: &[(&str, &str)] = &[
+    ("border", "bordure"), ("bricks", "bricks"), ("circle", "roundel"), ("creeper", "creeper charge"),
+    ("cross", "saltire"), ("curly_border", "bordure indented"), ("diagonal_left", "per bend sinister"),
+    ("diagonal_right", "per bend"), ("diagonal_up_left", "per bend inverted"),
+    ("diagonal_up_right", "per bend sinister inverted"), ("flow", "flow"), ("flower", "flower charge"),
+    ("globe", "globe"), ("gradient", "gradient"), ("gradient_up", "base gradient"), ("guster", "guster"),
+    ("half_horizontal", "per fess"), ("half_horizontal_bottom", "per fess inverted"),
+    ("half_vertical", "per pale"), ("half_vertical_right", "per pale inverted"), ("mojang", "thing"),
+    ("piglin", "snout"), ("rhombus", "lozenge"), ("skull", "skull charge"), ("small_stripes", "paly"),
+    ("square_bottom_left", "base dexter canton"), ("square_bottom_right", "base sinister canton"),
+    ("square_top_left", "chief dexter canton"), ("square_top_right", "chief sinister canton"),
+    ("straight_cross", "cross"), ("stripe_bottom", "base"), ("stripe_center", "pale"),
+    ("stripe_downleft", "bend sinister"), ("stripe_downright", "bend"), ("stripe_left", "pale dexter"),
+    ("stripe_middle", "fess"), ("stripe_right", "pale sinister"), ("stripe_top", "chief"),
+    ("triangle_bottom", "chevron"), ("triangle_top", "inverted chevron"),
+    ("triangles_bottom", "base indented"), ("triangles_top", "chief indented"),
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BlazonError {
+    #[error("A blazon needs at least the field, like 'Azure'")]
+    Empty,
+    #[error("Unknown tincture in '{0}'")]
+    UnknownTincture(String),
+    #[error("Unknown charge '{0}'")]
+    UnknownCharge(String),
+}
+
+/// The tincture of a dye, heraldry only has names for some of them
+pub fn tincture(color: Color) -> &'static str {
+    match color {
+        Color::White => "argent",
+        Color::Yellow => "or",
+        Color::Red => "gules",
+        Color::Blue => "azure",
+        Color::Green => "vert",
+        Color::Purple => "purpure",
+        Color::Black => "sable",
+        Color::Orange => "tenné",
+        Color::Magenta => "magenta",
+        Color::LightBlue => "light blue",
+        Color::Lime => "lime",
+        Color::Pink => "pink",
+        Color::Gray => "gray",
+        Color::LightGray => "light gray",
+        Color::Cyan => "cyan",
+        Color::Brown => "brown",
+    }
+}
+
+/// A dye from its tincture or its plain dye name (`light_blue` & `light blue` both work)
+fn parse_tincture(tincture: &str) -> Option<Color> {
+    let tincture = tincture.trim().to_lowercase().replace('_', " ");
+    let tincture = match tincture.as_str() {
+        "tenne" => "tenné",
+        "grey" => "gray",
+        "light grey" => "light gray",
+        other => other,
+    };
+
+    Color::all().iter().copied().find(|color| {
+        self::tincture(*color) == tincture || color.name().replace('_', " ") == tincture
+    })
+}
+
+/// The heraldic name of a pattern, patterns heraldry doesn't know keep their id without underscores
+fn charge(pattern: &str) -> String {
+    match CHARGES.iter().find(|(id, _)| *id == pattern) {
+        Some((_, charge)) => charge.to_string(),
+        None => pattern.replace('_', " "),
+    }
+}
+
+/// Writes out `banner` as a blazon
+pub fn blazon(
+    registry: &PatternRegistry,
+    banner: &BannerDescription,
+) -> Result<String, GenerationError> {
+    registry.check_layers(&banner.layers)?;
+
+    let field = tincture(banner.base);
+    let mut blazon = field[..1].to_uppercase() + &field[1..];
+    for (pattern_id, color) in &banner.layers {
+        let charge = charge(registry.name(*pattern_id).unwrap());
+        let article = if charge.starts_with(['a', 'e', 'i', 'o', 'u']) {
+            "an"
+        } else {
+            "a"
+        };
+        blazon.push_str(&format!(", {article} {charge} {}", tincture(*color)));
+    }
+
+    Ok(blazon)
+}
+
+/// Reads a blazon back into a banner, case doesn't matter & the articles (`a`, `an`, `and`) are optional
+pub fn parse_blazon(
+    registry: &PatternRegistry,
+    blazon: &str,
+) -> Result<BannerDescription, BlazonError> {
+    let mut parts = blazon.trim().trim_end_matches('.').split(',');
+    let field = parts
+        .next()
+        .filter(|f| !f.trim().is_empty())
+        .ok_or(BlazonError::Empty)?;
+    let base = parse_tincture(field)
+        .ok_or_else(|| BlazonError::UnknownTincture(field.trim().to_string()))?;
+
+    let mut layers = Vec::new();
+    for part in parts {
+        let words = part
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        let words = match words.first().map(String::as_str) {
+            Some("and") => &words[1..],
+            _ => &words[..],
+        };
+        let words = match words.first().map(String::as_str) {
+            Some("a" | "an") => &words[1..],
+            _ => words,
+        };
+
+        // tinctures are one or two words, the charge is everything before
+        let (charge, color) = (1..=2.min(words.len().saturating_sub(1)))
+            .rev()
+            .find_map(|n| {
+                let (charge, tincture) = words.split_at(words.len() - n);
+                Some((charge.join(" "), parse_tincture(&tincture.join(" "))?))
+            })
+            .ok_or_else(|| BlazonError::UnknownTincture(part.trim().to_string()))?;
+
+        let pattern_id = (0..registry.len())
+            .find(|id| self::charge(registry.name(*id).unwrap()) == charge)
+            .ok_or(BlazonError::UnknownCharge(charge))?;
+        layers.push((pattern_id, color));
+    }
+
+    Ok(BannerDescription { base, layers })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    fn registry() -> PatternRegistry {
+        let patterns = [
+            "border",
+            "creeper",
+            "stripe_top",
+            "triangle_top",
+            "new_pattern",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), Image::new(64, 64)))
+        .collect();
+        PatternRegistry::new(patterns).unwrap()
+    }
+
+    #[test]
+    fn writes_blazons() {
+        let registry = registry();
+        let banner = BannerDescription {
+            base: Color::Lime,
+            layers: vec![
+                (registry.id("creeper").unwrap(), Color::Black),
+                (registry.id("triangle_top").unwrap(), Color::Orange),
+                (registry.id("border").unwrap(), Color::Red),
+            ],
+        };
+
+        assert_eq!(
+            blazon(&registry, &banner).unwrap(),
+            "Lime, a creeper charge sable, an inverted chevron tenné, a bordure gules"
+        );
+    }
+
+    #[test]
+    fn reads_its_own_blazons() {
+        let registry = registry();
+        for base in Color::all() {
+            let banner = BannerDescription {
+                base: *base,
+                layers: (0..registry.len())
+                    .map(|id| (id, Color::all()[id * 3 % 16]))
+                    .collect(),
+            };
+            let blazon = blazon(&registry, &banner).unwrap();
+            assert_eq!(
+                parse_blazon(&registry, &blazon).unwrap(),
+                banner,
+                "{blazon}"
+            );
+        }
+    }
+
+    #[test]
+    fn reads_loose_blazons() {
+        let registry = registry();
+        let banner = parse_blazon(
+            &registry,
+            " AZURE, Chief Light_Blue, and a New Pattern grey.",
+        )
+        .unwrap();
+        assert_eq!(
+            banner,
+            BannerDescription {
+                base: Color::Blue,
+                layers: vec![
+                    (registry.id("stripe_top").unwrap(), Color::LightBlue),
+                    (registry.id("new_pattern").unwrap(), Color::Gray),
+                ],
+            }
+        );
+
+        assert_eq!(parse_blazon(&registry, ""), Err(BlazonError::Empty));
+        assert_eq!(
+            parse_blazon(&registry, "Azure, a bordure plaid"),
+            Err(BlazonError::UnknownTincture("a bordure plaid".to_string()))
+        );
+        assert_eq!(
+            parse_blazon(&registry, "Azure, a dragon or"),
+            Err(BlazonError::UnknownCharge("dragon".to_string()))
+        );
+    }
+}
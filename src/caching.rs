@@ -0,0 +1,114 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use std::hash::{Hash, Hasher};
+
+use minecraft_banners::{GeneratorVersion, PatternRegistry, Seed};
+
+use crate::query::{GetBannerQuery, TextQuery};
+
+/// Seeded renders never change (for the same registry), so they can be cached forever
+pub const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// Seeded renders without a pinned version, the default version could change
+pub const CACHE_UNPINNED: &str = "public, max-age=86400";
+/// Random renders are different every time
+pub const CACHE_NO_STORE: &str = "no-store";
+
+/// 64 bit FNV-1a, unlike `DefaultHasher` this is stable across builds & rust versions
+/// so ETags stay the same across restarts.
+pub struct Fnv64(u64);
+
+impl Fnv64 {
+    pub fn new() -> Self {
+        Fnv64(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Identifies the loaded set of patterns.
+///
+/// Adding, removing or changing any pattern changes every seed, so this goes into every ETag.
+pub fn registry_version(registry: &PatternRegistry) -> u64 {
+    let mut hasher = Fnv64::new();
+    for (id, img) in registry.patterns() {
+        id.hash(&mut hasher);
+        img.as_raw().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// A strong ETag for a deterministic render, `version` is `None` for renders without a seed
+pub fn banner_etag(
+    registry_version: u64,
+    version: Option<GeneratorVersion>,
+    seed: Option<Seed>,
+    query: &GetBannerQuery,
+    profile: Option<u64>,
+    format: &str,
+) -> HeaderValue {
+    let mut hasher = Fnv64::new();
+    registry_version.hash(&mut hasher);
+    version.hash(&mut hasher);
+    seed.hash(&mut hasher);
+    query.hash(&mut hasher);
+    profile.hash(&mut hasher);
+    format.hash(&mut hasher);
+
+    etag(hasher)
+}
+
+/// A strong ETag for a rendered `/text`, which is just as deterministic as `/create`
+pub fn text_etag(registry_version: u64, query: &TextQuery, format: &str) -> HeaderValue {
+    let mut hasher = Fnv64::new();
+    registry_version.hash(&mut hasher);
+    query.hash(&mut hasher);
+    format.hash(&mut hasher);
+
+    etag(hasher)
+}
+
+fn etag(hasher: Fnv64) -> HeaderValue {
+    // only hex digits and quotes, always a valid header value
+    HeaderValue::from_str(&format!("\"{:016x}\"", hasher.finish())).unwrap()
+}
+
+/// If the client already has the response with this `etag` (`If-None-Match`)
+pub fn is_not_modified(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Some(etag) = etag.to_str().ok() else {
+        return false;
+    };
+
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// `304 Not Modified` with the same caching headers as the full response
+pub fn not_modified(etag: HeaderValue, cache_control: &'static str) -> Response {
+    (
+        StatusCode::NOT_MODIFIED,
+        [
+            (header::ETAG, etag),
+            (
+                header::CACHE_CONTROL,
+                HeaderValue::from_static(cache_control),
+            ),
+        ],
+    )
+        .into_response()
+}
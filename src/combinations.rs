@@ -0,0 +1,120 @@
+use mcb::{Banner, Color};
+
+use crate::GenerationError;
+
+/// Exact amount of banners for a set of patterns, split by how many layers they have.
+///
+/// Every layer is any pattern in any color, and every banner has one of the base colors.
+/// Counts are checked, a registry too big to count errors instead of wrapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Combinations {
+    /// `per_layer[n]` is how many banners have exactly `n` layers
+    pub per_layer: Vec<u128>,
+    pub total: u128,
+}
+
+impl Combinations {
+    /// Counts banners with up to `max_layers` layers
+    pub fn new(pattern_len: usize, max_layers: usize) -> Result<Self, GenerationError> {
+        Self::with_colors(pattern_len, Color::all().len(), max_layers)
+    }
+
+    /// Same as [`Combinations::new`] when only `color_len` of the colors can be used
+    pub fn with_colors(
+        pattern_len: usize,
+        color_len: usize,
+        max_layers: usize,
+    ) -> Result<Self, GenerationError> {
+        let colors = color_len as u128;
+        let layer_combos = (pattern_len as u128)
+            .checked_mul(colors)
+            .ok_or(GenerationError::Overflow)?;
+
+        let mut per_layer = Vec::with_capacity(max_layers + 1);
+        // banners with 0 layers, only the base color
+        let mut count = colors;
+        let mut total: u128 = 0;
+        for n in 0..=max_layers {
+            if n > 0 {
+                count = count
+                    .checked_mul(layer_combos)
+                    .ok_or(GenerationError::Overflow)?;
+            }
+            total = total.checked_add(count).ok_or(GenerationError::Overflow)?;
+            per_layer.push(count);
+        }
+
+        Ok(Combinations { per_layer, total })
+    }
+
+    /// For vanilla banners, up to [`Banner::VANILLA_MAX_PATTERN_SIZE`] layers
+    pub fn vanilla(pattern_len: usize) -> Result<Self, GenerationError> {
+        Self::new(pattern_len, Banner::VANILLA_MAX_PATTERN_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BannerGenerator, GenerateOptions, Image, PatternRegistry};
+
+    #[test]
+    fn matches_vanilla_count() {
+        // every vanilla pattern, the number in the readme
+        let combinations = Combinations::vanilla(42).unwrap();
+        assert_eq!(combinations.total, 1_475_646_641_940_097_552);
+        assert_eq!(
+            combinations.per_layer.iter().sum::<u128>(),
+            combinations.total
+        );
+        assert_eq!(combinations.per_layer[0], 16);
+    }
+
+    #[test]
+    fn counts_past_u64() {
+        // (64 * 16)^6 * 16 = 2^64 for the last layer alone
+        let combinations = Combinations::vanilla(64).unwrap();
+        assert_eq!(combinations.per_layer[6], 1 << 64);
+        assert!(combinations.total > u64::MAX as u128);
+    }
+
+    #[test]
+    fn overflow_errors() {
+        // (2^16 * 16)^6 * 16 = 2^124
+        assert!(Combinations::new(1 << 16, 6).is_ok());
+        assert!(matches!(
+            Combinations::new(1 << 16, 7),
+            Err(GenerationError::Overflow)
+        ));
+        assert!(matches!(
+            Combinations::vanilla(usize::MAX),
+            Err(GenerationError::Overflow)
+        ));
+    }
+
+    #[test]
+    fn seeds_past_u64_generate() {
+        let patterns = (0..100)
+            .map(|i| (format!("{i:03}"), Image::new(Banner::X, Banner::Y)))
+            .collect();
+        let registry = PatternRegistry::new(patterns).unwrap();
+        let generator = BannerGenerator::new(&registry);
+
+        let max = generator.combinations().unwrap().total;
+        assert!(max > u64::MAX as u128);
+
+        let seed = u64::MAX as u128 + 12345;
+        let (_, first) = generator
+            .generate(Some(seed), GenerateOptions::default())
+            .unwrap();
+        let (_, second) = generator
+            .generate(Some(seed), GenerateOptions::default())
+            .unwrap();
+        assert_eq!(first, second);
+
+        let (_, other) = generator
+            .generate(Some(seed + 1), GenerateOptions::default())
+            .unwrap();
+        assert_ne!(first, other);
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use std::{convert::Infallible, env, str::FromStr};
+
+use minecraft_banners::GeneratorVersion;
+
+use crate::logging::LogFormat;
+
+/// Server configuration, read from `BANNERS_*` environment variables.
+///
+/// Everything has a default so the server runs fine without any of them set.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Rate limit for the cheap JSON/text endpoints
+    pub json_limit: RateLimit,
+    /// Rate limit for endpoints that render an image
+    pub render_limit: RateLimit,
+    /// Use the first `X-Forwarded-For` address as the client ip (when behind a reverse proxy)
+    pub trust_forwarded_for: bool,
+    /// Max amount of pixels (`width * height`) an image response can have
+    pub max_output_pixels: u64,
+    /// Max amount of encoded banners kept in the render cache
+    pub cache_max_entries: usize,
+    /// Max amount of bytes the render cache can take up
+    pub cache_max_bytes: usize,
+    /// `pretty` or `json`
+    pub log_format: LogFormat,
+    /// An `EnvFilter` directive like `info` or `minecraft_banners=debug`, `RUST_LOG` overrides it
+    pub log_level: String,
+    /// Origins allowed to call the api from a browser, `*` for any, empty disables CORS
+    pub cors_origins: List,
+    /// Methods allowed for cross-origin requests
+    pub cors_methods: List,
+    /// Response headers scripts are allowed to read
+    pub cors_expose_headers: List,
+    /// Version used for seeds that aren't pinned to one, changing it changes every unpinned link
+    pub generator_version: GeneratorVersion,
+    /// JSON file with the weighted generation profiles for `?profile=`, it's fine if it doesn't exist
+    pub profiles_path: String,
+}
+
+/// A comma separated list, empty entries are skipped
+#[derive(Debug, Clone, Default)]
+pub struct List(pub Vec<String>);
+
+impl FromStr for List {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(List(
+            s.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        ))
+    }
+}
+
+impl From<&str> for List {
+    fn from(s: &str) -> Self {
+        let Ok(list) = s.parse();
+        list
+    }
+}
+
+/// A token bucket rate limit, a `per_second` of 0 disables it
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self> {
+        Ok(Config {
+            json_limit: RateLimit {
+                per_second: var("BANNERS_JSON_RATE", 20.0)?,
+                burst: var("BANNERS_JSON_BURST", 40.0)?,
+            },
+            render_limit: RateLimit {
+                per_second: var("BANNERS_RENDER_RATE", 10.0)?,
+                burst: var("BANNERS_RENDER_BURST", 20.0)?,
+            },
+            trust_forwarded_for: var("BANNERS_TRUST_FORWARDED_FOR", false)?,
+            // 1024x2048
+            max_output_pixels: var("BANNERS_MAX_OUTPUT_PIXELS", 2_097_152)?,
+            cache_max_entries: var("BANNERS_CACHE_MAX_ENTRIES", 10_000)?,
+            // 64 MiB
+            cache_max_bytes: var("BANNERS_CACHE_MAX_BYTES", 67_108_864)?,
+            log_format: var("BANNERS_LOG_FORMAT", LogFormat::Pretty)?,
+            log_level: var("BANNERS_LOG_LEVEL", "info".to_string())?,
+            cors_origins: var("BANNERS_CORS_ORIGINS", List::default())?,
+            cors_methods: var("BANNERS_CORS_METHODS", List::from("GET, POST"))?,
+            cors_expose_headers: var(
+                "BANNERS_CORS_EXPOSE_HEADERS",
+                List::from(
+                    "x-banner-seed, x-banner-version, x-request-id, etag, location, retry-after",
+                ),
+            )?,
+            // v1 is what every link before versioning was made with
+            generator_version: var("BANNERS_GENERATOR_VERSION", GeneratorVersion::V1)?,
+            profiles_path: var("BANNERS_PROFILES", "profiles.json".to_string())?,
+        })
+    }
+}
+
+/// Parses an environment variable, falling back to `default` if it isn't set
+fn var<T>(name: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid value for {name}: '{value}'")),
+        Err(_) => Ok(default),
+    }
+}
@@ -0,0 +1,52 @@
+use anyhow::{Context, Result};
+use axum::http::{HeaderName, HeaderValue, Method, header};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{config::Config, logging::X_REQUEST_ID};
+
+/// Builds the CORS layer from the `cors_*` fields of [`Config`].
+///
+/// `None` when no origins are configured, browsers then block cross-origin calls like before.
+pub fn layer(config: &Config) -> Result<Option<CorsLayer>> {
+    let origins = &config.cors_origins.0;
+    if origins.is_empty() {
+        return Ok(None);
+    }
+
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|o| HeaderValue::try_from(o.as_str()))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Invalid origin in BANNERS_CORS_ORIGINS")?;
+        AllowOrigin::list(origins)
+    };
+
+    let methods = config
+        .cors_methods
+        .0
+        .iter()
+        .map(|m| Method::try_from(m.to_ascii_uppercase().as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Invalid method in BANNERS_CORS_METHODS")?;
+
+    let exposed = config
+        .cors_expose_headers
+        .0
+        .iter()
+        .map(|h| HeaderName::try_from(h.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Invalid header in BANNERS_CORS_EXPOSE_HEADERS")?;
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(methods)
+            // conditional requests for the immutable banners & image uploads to /solve
+            .allow_headers([header::IF_NONE_MATCH, header::CONTENT_TYPE, X_REQUEST_ID])
+            .expose_headers(exposed)
+            .max_age(std::time::Duration::from_secs(3600)),
+    ))
+}
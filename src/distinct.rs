@@ -0,0 +1,180 @@
+//! How many banners actually look different.
+//!
+//! [`Combinations`] counts layer lists, but lots of them render the same banner: a layer is
+//! invisible once the opaque pixels of the layers above it cover every pixel it touches
+//! (see [`mcb::canonical`]). Only counting lists where every layer shows gives an upper bound
+//! on the distinct banners, every banner has at least one such list.
+//!
+//! Colors don't change what covers what, so this walks the layers top to bottom keeping
+//! track of the covered pixels, there are few enough different covered areas to count them all.
+
+use mcb::{Banner, Color, Pattern, composite::PIXELS};
+use std::collections::HashMap;
+
+use crate::{Combinations, GenerationError, PatternRegistry};
+
+const WORDS: usize = PIXELS.div_ceil(64);
+
+/// A set of banner pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Pixels([u64; WORDS]);
+
+impl Pixels {
+    const EMPTY: Self = Pixels([0; WORDS]);
+
+    fn insert(&mut self, pixel: usize) {
+        self.0[pixel / 64] |= 1 << (pixel % 64);
+    }
+
+    fn union(&self, other: &Pixels) -> Pixels {
+        let mut union = *self;
+        for (word, other) in union.0.iter_mut().zip(other.0) {
+            *word |= other;
+        }
+        union
+    }
+
+    fn is_subset(&self, other: &Pixels) -> bool {
+        self.0
+            .iter()
+            .zip(other.0)
+            .all(|(word, other)| word & !other == 0)
+    }
+
+    fn is_full(&self) -> bool {
+        (0..PIXELS).all(|pixel| self.0[pixel / 64] & (1 << (pixel % 64)) != 0)
+    }
+}
+
+/// The pixels a pattern touches at all & the ones it fully covers
+struct Mask {
+    touched: Pixels,
+    opaque: Pixels,
+}
+
+impl Mask {
+    fn new(texture: &Pattern) -> Self {
+        let mut mask = Mask {
+            touched: Pixels::EMPTY,
+            opaque: Pixels::EMPTY,
+        };
+        for (x, y, pixel) in texture.enumerate_pixels() {
+            let i = (x + y * Banner::X) as usize;
+            if pixel[3] > 0 {
+                mask.touched.insert(i);
+            }
+            if pixel[3] == 255 {
+                mask.opaque.insert(i);
+            }
+        }
+        mask
+    }
+}
+
+/// Upper bound of the visually distinct banners with up to `max_layers` layers.
+///
+/// `per_layer[n]` counts the banners that need exactly `n` layers, the base color only counts
+/// when the layers don't cover all of it.
+pub fn distinct_combinations(
+    registry: &PatternRegistry,
+    max_layers: usize,
+) -> Result<Combinations, GenerationError> {
+    let masks = registry
+        .patterns()
+        .iter()
+        .map(|(_, texture)| Mask::new(&Pattern::new(texture.clone())))
+        .collect::<Vec<_>>();
+    let colors = Color::all().len() as u128;
+    let checked_add = |a: u128, b: u128| a.checked_add(b).ok_or(GenerationError::Overflow);
+    let checked_mul = |a: u128, b: u128| a.checked_mul(b).ok_or(GenerationError::Overflow);
+
+    // how many pattern lists (from the top down) leave these pixels covered
+    let mut states = HashMap::from([(Pixels::EMPTY, 1u128)]);
+    let mut per_layer = vec![colors];
+
+    for n in 1..=max_layers {
+        let last = n == max_layers;
+        let mut next = HashMap::new();
+        let mut count: u128 = 0;
+
+        for (covered, lists) in &states {
+            for mask in &masks {
+                if mask.touched.is_subset(covered) {
+                    continue;
+                }
+
+                let covered = covered.union(&mask.opaque);
+                let bases = if covered.is_full() { 1 } else { colors };
+                count = checked_add(count, checked_mul(*lists, bases)?)?;
+                // the bottom layer doesn't need to remember what it covers
+                if !last {
+                    let lists_below = next.entry(covered).or_insert(0);
+                    *lists_below = checked_add(*lists_below, *lists)?;
+                }
+            }
+        }
+
+        per_layer.push(checked_mul(count, colors.pow(n as u32))?);
+        states = next;
+    }
+
+    let total = per_layer
+        .iter()
+        .try_fold(0, |total, count| checked_add(total, *count))?;
+    Ok(Combinations { per_layer, total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BannerDescription, test_support::shape_registry};
+    use std::collections::HashSet;
+
+    #[test]
+    fn full_cover_hides_everything_below() {
+        let registry = shape_registry(&[|_, _| true]);
+        let distinct = distinct_combinations(&registry, 2).unwrap();
+
+        // the base alone, or one layer hiding the base, a second layer hides the first
+        assert_eq!(distinct.per_layer, vec![16, 16, 0]);
+        assert_eq!(distinct.total, 32);
+    }
+
+    #[test]
+    fn bounds_rendering_every_banner() {
+        let registry = shape_registry(&[|x, _| x < 10, |_, y| y < 20]);
+        let distinct = distinct_combinations(&registry, 2).unwrap();
+
+        let mut banners = Vec::new();
+        for base in Color::all() {
+            banners.push(BannerDescription {
+                base: *base,
+                layers: vec![],
+            });
+            for a in 0..registry.len() {
+                for a_color in Color::all() {
+                    banners.push(BannerDescription {
+                        base: *base,
+                        layers: vec![(a, *a_color)],
+                    });
+                    for b in 0..registry.len() {
+                        for b_color in Color::all() {
+                            banners.push(BannerDescription {
+                                base: *base,
+                                layers: vec![(a, *a_color), (b, *b_color)],
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        let rendered = banners
+            .iter()
+            .map(|banner| registry.render(banner).unwrap().into_raw())
+            .collect::<HashSet<_>>();
+
+        // a layer dyed like what's under it can still vanish, so it's only close
+        assert!(distinct.total >= rendered.len() as u128);
+        assert!(distinct.total < Combinations::new(registry.len(), 2).unwrap().total);
+    }
+}
@@ -1,83 +1,430 @@
-use anyhow::{Result, anyhow};
-use axum::extract::Path;
 use mcb::*;
-use rand::{Rng, SeedableRng};
+use rand::{Rng, SeedableRng, distr::weighted::WeightedIndex, seq::IndexedRandom};
 use rand_chacha::ChaCha8Rng;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
 
-use crate::{Image, random_color};
+use crate::{
+    Combinations, PatternRegistry,
+    style::{self, Style},
+};
 
-pub fn get_possible_combinations(pattern_len: usize) -> u64 {
-    const C: u64 = Color::all().len() as u64;
-    let p = pattern_len as u64;
-    let r = (p * C) as u64;
-    C * (r.pow(6) + r.pow(5) + r.pow(4) + r.pow(3) + r.pow(2) + r + 1)
+mod v1;
+mod v2;
+
+/// Seeds go up to the [`Combinations::total`] of a registry, which can go past `u64`.
+///
+/// Seeds that fit in a `u64` seed the rng exactly like they always have.
+pub type Seed = u128;
+
+/// Overrides one generated layer, `None` keeps the random one, as does a `None` pattern or color
+pub type LayerOverride = Option<(Option<usize>, Option<Color>)>;
+
+/// A banner as data: the base color & every layer from bottom to top
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BannerDescription {
+    pub base: Color,
+    /// `(pattern id, color)`, see [`PatternRegistry`] for the ids
+    pub layers: Vec<(usize, Color)>,
+}
+
+/// Tweaks to a seeded banner, the default is the plain seeded banner
+#[derive(Debug, Clone, Default)]
+pub struct GenerateOptions {
+    /// Replaces the random base color
+    pub base_color: Option<Color>,
+    /// Replaces random layers, up to [`Banner::VANILLA_MAX_PATTERN_SIZE`]
+    pub layers: Vec<LayerOverride>,
+    /// Cuts the banner down to this many layers
+    pub max_layers: Option<usize>,
+}
+
+/// Limits the patterns & colors random banners are made of, the default allows everything.
+///
+/// Banners are uniform over what's left, which changes how many there are & so every seed:
+/// a seed is only the same banner with the same constraints. The order of ids doesn't matter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Constraints {
+    /// Colors for the base & every layer, `None` allows all of them
+    pub colors: Option<Vec<Color>>,
+    /// Pattern ids layers can use, `None` allows all of them
+    pub patterns: Option<Vec<usize>>,
+    /// Pattern ids that are never used, even when they're in `patterns`
+    pub exclude_patterns: Vec<usize>,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_none() && self.patterns.is_none() && self.exclude_patterns.is_empty()
+    }
+
+    /// The allowed colors in id order
+    pub fn colors(&self) -> Vec<Color> {
+        let mut colors = match &self.colors {
+            Some(colors) => colors.clone(),
+            None => Color::all().to_vec(),
+        };
+        colors.sort_unstable();
+        colors.dedup();
+        colors
+    }
+
+    /// The allowed pattern ids out of `0..pattern_len` in order
+    pub fn patterns(&self, pattern_len: usize) -> Vec<usize> {
+        let mut patterns = match &self.patterns {
+            Some(patterns) => patterns.clone(),
+            None => (0..pattern_len).collect(),
+        };
+        patterns.retain(|p| !self.exclude_patterns.contains(p));
+        patterns.sort_unstable();
+        patterns.dedup();
+        patterns
+    }
+}
+
+/// Weights random banners are picked by, instead of every banner being equally likely.
+///
+/// Seeds keep their range but only mean the same banner with the same profile.
+/// Patterns & colors without a weight weigh `1`, a weight of `0` never picks them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    /// `(pattern id, weight)`
+    pub patterns: Vec<(usize, f64)>,
+    /// `(color, weight)`, for the base & every layer
+    pub colors: Vec<(Color, f64)>,
+    /// Weight of every layer count starting at `0`, the missing ones weigh `0`.
+    /// Empty keeps the count weighted by how many banners have it
+    pub layers: Vec<f64>,
+}
+
+impl Profile {
+    /// Weights of the `allowed` patterns & colors, in the same order
+    fn weights(
+        &self,
+        patterns: &[usize],
+        colors: &[Color],
+    ) -> Result<v2::Weights, GenerationError> {
+        fn index(
+            weights: impl IntoIterator<Item = f64>,
+            what: &'static str,
+        ) -> Result<WeightedIndex<f64>, GenerationError> {
+            WeightedIndex::new(weights).map_err(|_| GenerationError::InvalidWeights { what })
+        }
+        // the last one wins, like query args
+        fn weight<T: PartialEq>(weights: &[(T, f64)], item: T) -> f64 {
+            weights
+                .iter()
+                .rfind(|(i, _)| *i == item)
+                .map_or(1.0, |(_, weight)| *weight)
+        }
+
+        let layers = match self.layers.as_slice() {
+            [] => None,
+            // without patterns there's nothing to put on the layers
+            _ if patterns.is_empty() => Some(index([1.0], "layer count")?),
+            layers => Some(index(
+                layers
+                    .iter()
+                    .copied()
+                    .take(Banner::VANILLA_MAX_PATTERN_SIZE + 1),
+                "layer count",
+            )?),
+        };
+        let pattern_weights = match patterns {
+            // never sampled, there are no layers
+            [] => index([1.0], "pattern")?,
+            patterns => index(
+                patterns.iter().map(|p| weight(&self.patterns, *p)),
+                "pattern",
+            )?,
+        };
+
+        Ok(v2::Weights {
+            patterns: pattern_weights,
+            colors: index(colors.iter().map(|c| weight(&self.colors, *c)), "color")?,
+            layers,
+        })
+    }
 }
 
-pub fn generate_seed(pattern_len: usize) -> u64 {
-    let mut rng = ChaCha8Rng::from_os_rng();
-    rng.random_range(0..get_possible_combinations(pattern_len))
+#[derive(Debug, Error)]
+pub enum GenerationError {
+    #[error("Seed is too big, must be less than {max}")]
+    SeedOutOfRange { max: Seed },
+    #[error("Too many layers provided")]
+    TooManyLayers,
+    #[error("Can't override layer {layer}, the banner only has {layers} layers")]
+    MissingLayer { layer: usize, layers: usize },
+    #[error("Pattern {id} doesn't exist, there are only {len} patterns")]
+    UnknownPattern { id: usize, len: usize },
+    #[error("Too many patterns to count every combination")]
+    Overflow,
+    #[error("{version} can't be constrained, use v2 or later")]
+    UnsupportedConstraints { version: GeneratorVersion },
+    #[error("No banner fits the constraints, at least one color is needed")]
+    NoColors,
+    #[error("{version} can't use profiles, use v2 or later")]
+    UnsupportedProfile { version: GeneratorVersion },
+    #[error(
+        "Invalid {what} weights, none can be negative & at least one allowed {what} has to weigh more than 0"
+    )]
+    InvalidWeights { what: &'static str },
+    #[error("Couldn't render the banner: {0}")]
+    Render(anyhow::Error),
 }
 
-pub fn generate_pattern_list(
-    rng: &mut ChaCha8Rng,
-    pattern_ref: &Vec<(String, Image)>,
-    layers: Vec<Option<(Option<usize>, Option<Color>)>>,
-    max_layers: Option<usize>,
-) -> Result<Vec<(usize, Color)>> {
-    let mut patterns = Vec::with_capacity(6);
-    for _ in 0..get_amount_of_layers(rng, pattern_ref.len()) {
-        let pattern = rng.random_range(0..pattern_ref.len() - 1);
-        let color = random_color(rng);
-        patterns.push((pattern, *color));
+/// Which algorithm turns a seed into a banner.
+///
+/// Every version is its own frozen implementation, fixing a bug means adding a new version
+/// so seeds that are already out there keep rendering the same banner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GeneratorVersion {
+    /// The original algorithm, it never picks the last pattern
+    V1,
+    /// Every pattern is reachable & overriding only a pattern keeps the layer's own color
+    V2,
+}
+
+impl GeneratorVersion {
+    pub const LATEST: Self = GeneratorVersion::V2;
+    pub const ALL: [Self; 2] = [GeneratorVersion::V1, GeneratorVersion::V2];
+}
+
+impl fmt::Display for GeneratorVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorVersion::V1 => write!(f, "v1"),
+            GeneratorVersion::V2 => write!(f, "v2"),
+        }
     }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown generator version, expected one of v1, v2")]
+pub struct ParseVersionError;
+
+/// Accepts `v2`, `V2` & `2`
+impl FromStr for GeneratorVersion {
+    type Err = ParseVersionError;
 
-    let set_patterns = layers;
-    if set_patterns.len() > Banner::VANILLA_MAX_PATTERN_SIZE {
-        return Err(anyhow!("Too many layers provided"));
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let number = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        match number {
+            "1" => Ok(GeneratorVersion::V1),
+            "2" => Ok(GeneratorVersion::V2),
+            _ => Err(ParseVersionError),
+        }
     }
+}
 
-    // override any random pattern if a set one was provided
-    for (i, set_p) in set_patterns.into_iter().enumerate() {
-        if let Some((pattern_id, color)) = set_p {
-            let new_pattern = match (pattern_id, color) {
-                (None, None) => patterns[i],
-                (Some(i), None) => (i, patterns[i].1),
-                (None, Some(c)) => (patterns[i].0, c),
-                (Some(i), Some(c)) => (i, c),
-            };
-            patterns[i] = new_pattern;
+/// A seed that can be pinned to a [`GeneratorVersion`], written as `v2-12345` or a plain `12345`.
+///
+/// Plain seeds use whatever version is the default, pinned ones render the same banner forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VersionedSeed {
+    pub version: Option<GeneratorVersion>,
+    pub seed: Seed,
+}
+
+impl VersionedSeed {
+    pub fn pinned(version: GeneratorVersion, seed: Seed) -> Self {
+        VersionedSeed {
+            version: Some(version),
+            seed,
         }
     }
+}
 
-    // technically you can just build your own banners via this
-    // it will still run the randomizer for all 6 layers etc
-    // and you can then just override them all and or just a few
-    // and then truncate it to the amount you want
-    if let Some(max_layers) = max_layers {
-        patterns.truncate(max_layers);
+impl fmt::Display for VersionedSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            Some(version) => write!(f, "{version}-{}", self.seed),
+            None => write!(f, "{}", self.seed),
+        }
     }
+}
 
-    Ok(patterns)
+/// Turns seeds into banners for a [`PatternRegistry`].
+///
+/// Seeds are only stable for the same set of patterns & [`GeneratorVersion`],
+/// adding or removing a pattern changes all of them.
+#[derive(Debug, Clone, Copy)]
+pub struct BannerGenerator<'a> {
+    registry: &'a PatternRegistry,
+    version: GeneratorVersion,
+    constraints: Option<&'a Constraints>,
+    style: Style,
+    profile: Option<&'a Profile>,
 }
 
-fn get_amount_of_layers(rng: &mut ChaCha8Rng, pattern_len: usize) -> usize {
-    const COLORS: usize = Color::all().len();
+impl<'a> BannerGenerator<'a> {
+    /// A generator using [`GeneratorVersion::LATEST`]
+    pub fn new(registry: &'a PatternRegistry) -> Self {
+        Self::with_version(registry, GeneratorVersion::LATEST)
+    }
+
+    pub fn with_version(registry: &'a PatternRegistry, version: GeneratorVersion) -> Self {
+        BannerGenerator {
+            registry,
+            version,
+            constraints: None,
+            style: Style::Random,
+            profile: None,
+        }
+    }
+
+    /// Only generates banners within `constraints`, which gives every seed a different banner
+    pub fn constrained(self, constraints: &'a Constraints) -> Self {
+        BannerGenerator {
+            constraints: Some(constraints),
+            ..self
+        }
+    }
+
+    /// Picks banners in `style`, the same seed & style always give the same banner
+    pub fn styled(self, style: Style) -> Self {
+        BannerGenerator { style, ..self }
+    }
+
+    /// Picks banners by the weights of `profile`, `None` keeps every banner equally likely
+    pub fn weighted(self, profile: Option<&'a Profile>) -> Self {
+        BannerGenerator { profile, ..self }
+    }
+
+    pub fn version(&self) -> GeneratorVersion {
+        self.version
+    }
+
+    pub fn registry(&self) -> &'a PatternRegistry {
+        self.registry
+    }
+
+    /// How many banners there are, and how many different seeds
+    pub fn combinations(&self) -> Result<Combinations, GenerationError> {
+        if self.version == GeneratorVersion::V1 && self.profile.is_some() {
+            return Err(GenerationError::UnsupportedProfile {
+                version: self.version,
+            });
+        }
+        let Some(constraints) = self.constraints else {
+            return Combinations::vanilla(self.registry.len());
+        };
+        if self.version == GeneratorVersion::V1 && !constraints.is_empty() {
+            return Err(GenerationError::UnsupportedConstraints {
+                version: self.version,
+            });
+        }
+
+        let patterns = constraints.patterns(self.registry.len());
+        let colors = constraints.colors();
+        if let Some(id) = patterns.last().filter(|id| **id >= self.registry.len()) {
+            return Err(GenerationError::UnknownPattern {
+                id: *id,
+                len: self.registry.len(),
+            });
+        }
+        if colors.is_empty() {
+            return Err(GenerationError::NoColors);
+        }
+
+        Combinations::with_colors(
+            patterns.len(),
+            colors.len(),
+            Banner::VANILLA_MAX_PATTERN_SIZE,
+        )
+    }
+
+    /// A new random seed within [`Combinations::total`]
+    pub fn random_seed(&self) -> Result<Seed, GenerationError> {
+        let mut rng = ChaCha8Rng::from_os_rng();
+        Ok(random_below(&mut rng, self.combinations()?.total))
+    }
+
+    /// Generates the banner for `seed` (a random one if `None`), returning the seed that was used
+    pub fn generate(
+        &self,
+        seed: Option<Seed>,
+        options: GenerateOptions,
+    ) -> Result<(Seed, BannerDescription), GenerationError> {
+        let (seed, mut rng) = self.rng_from_seed(seed)?;
+        let combinations = self.combinations()?;
+
+        if options.layers.len() > Banner::VANILLA_MAX_PATTERN_SIZE {
+            return Err(GenerationError::TooManyLayers);
+        }
+
+        let constraints = self.constraints.cloned().unwrap_or_default();
+        let patterns = constraints.patterns(self.registry.len());
+        let colors = constraints.colors();
+        let weights = match self.profile {
+            Some(profile) => Some(profile.weights(&patterns, &colors)?),
+            None => None,
+        };
+        let allowed = v2::Allowed {
+            patterns,
+            colors,
+            weights,
+        };
+        let fixed = options
+            .layers
+            .iter()
+            .map(|layer| layer.is_some())
+            .collect::<Vec<_>>();
 
-    let mut total: u64 = 0;
-    let mut counts = Vec::with_capacity(Banner::VANILLA_MAX_PATTERN_SIZE);
+        let mut banner = match self.version {
+            GeneratorVersion::V1 => v1::generate(&mut rng, &combinations, self.registry, options)?,
+            GeneratorVersion::V2 => v2::generate(&mut rng, &combinations, &allowed, options)?,
+        };
+        self.registry.check_layers(&banner.layers)?;
 
-    for n in 0..=Banner::VANILLA_MAX_PATTERN_SIZE {
-        let layer_combos = (pattern_len * COLORS) as u64;
-        let count = COLORS as u64 * layer_combos.pow(n as u32);
-        counts.push(count);
-        total += count;
+        // redraws go on with the same rng, the random banner itself stays the same
+        if self.style == Style::Pretty {
+            banner = style::prettify(
+                self.registry,
+                banner,
+                &fixed,
+                |rng| allowed.layer(rng),
+                &mut rng,
+            )
+            .map_err(GenerationError::Render)?;
+        }
+
+        Ok((seed, banner))
     }
 
-    let choice = rng.random_range(0..total);
+    fn rng_from_seed(&self, seed: Option<Seed>) -> Result<(Seed, ChaCha8Rng), GenerationError> {
+        let seed = match seed {
+            Some(seed) => seed,
+            None => self.random_seed()?,
+        };
+
+        let max = self.combinations()?.total;
+        if seed >= max {
+            return Err(GenerationError::SeedOutOfRange { max });
+        }
 
-    let mut cumulative: u64 = 0;
+        let rng = match u64::try_from(seed) {
+            Ok(seed) => ChaCha8Rng::seed_from_u64(seed),
+            // only reachable for registries with more than u64::MAX combinations
+            Err(_) => {
+                let mut bytes = [0; 32];
+                bytes[..16].copy_from_slice(&seed.to_le_bytes());
+                ChaCha8Rng::from_seed(bytes)
+            }
+        };
+
+        Ok((seed, rng))
+    }
+}
+
+/// Picks how many layers a banner gets, weighted by how many banners have that many layers
+fn get_amount_of_layers(rng: &mut ChaCha8Rng, combinations: &Combinations) -> usize {
+    let choice = random_below(rng, combinations.total);
+
+    let mut cumulative: u128 = 0;
     let mut num_layers = 0;
-    for (i, &count) in counts.iter().enumerate() {
+    for (i, &count) in combinations.per_layer.iter().enumerate() {
         if choice < cumulative + count {
             num_layers = i;
             break;
@@ -88,21 +435,41 @@ fn get_amount_of_layers(rng: &mut ChaCha8Rng, pattern_len: usize) -> usize {
     num_layers
 }
 
-pub fn get_rng_from_seed(seed: Option<Path<String>>, pattern_len: usize) -> Result<ChaCha8Rng> {
-    let seed = seed
-        .map(|s| match s.parse::<u64>() {
-            Ok(s) => Some(s),
-            Err(_) => None,
-        })
-        .flatten()
-        .unwrap_or_else(|| generate_seed(pattern_len));
+/// Checks that an override at `layer` has a layer to override
+fn check_override(layer: usize, layers: &[(usize, Color)]) -> Result<(), GenerationError> {
+    if layer >= layers.len() {
+        return Err(GenerationError::MissingLayer {
+            layer,
+            layers: layers.len(),
+        });
+    }
+    Ok(())
+}
 
-    let possible_combs = get_possible_combinations(pattern_len);
-    if seed > possible_combs {
-        return Err(anyhow!(
-            "Seed is too big, must be less than {possible_combs}"
-        ));
+/// A random number in `0..max`.
+///
+/// Draws a `u64` whenever `max` fits in one, so seeds stay the same as before counts were `u128`.
+fn random_below(rng: &mut ChaCha8Rng, max: u128) -> u128 {
+    match u64::try_from(max) {
+        Ok(max) => rng.random_range(0..max) as u128,
+        Err(_) => rng.random_range(0..max),
     }
+}
 
-    Ok(ChaCha8Rng::seed_from_u64(seed))
+fn random_color(rng: &mut ChaCha8Rng) -> &Color {
+    Color::all().choose(rng).unwrap()
+}
+
+/// Parses a seed from the path, anything that isn't a valid (optionally versioned) seed counts as no seed
+pub fn parse_seed(seed: &str) -> Option<VersionedSeed> {
+    match seed.split_once('-') {
+        Some((version, seed)) => Some(VersionedSeed::pinned(
+            version.parse().ok()?,
+            seed.parse().ok()?,
+        )),
+        None => Some(VersionedSeed {
+            version: None,
+            seed: seed.parse().ok()?,
+        }),
+    }
 }
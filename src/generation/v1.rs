@@ -0,0 +1,62 @@
+//! The original algorithm, frozen so every seed from before `v2` keeps its banner.
+//!
+//! Known quirks, kept on purpose:
+//! - patterns are picked from `0..len - 1` so the last pattern is never picked (and it needs at least 2)
+//! - overriding only the pattern of a layer takes the color of the layer at the *pattern id*
+
+use super::{check_override, get_amount_of_layers, random_color};
+use crate::{BannerDescription, Combinations, GenerateOptions, GenerationError, PatternRegistry};
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+
+pub(super) fn generate(
+    rng: &mut ChaCha8Rng,
+    combinations: &Combinations,
+    registry: &PatternRegistry,
+    options: GenerateOptions,
+) -> Result<BannerDescription, GenerationError> {
+    let pattern_len = registry.len();
+
+    let mut patterns = Vec::with_capacity(6);
+    for _ in 0..get_amount_of_layers(rng, combinations) {
+        let pattern = rng.random_range(0..pattern_len - 1);
+        let color = random_color(rng);
+        patterns.push((pattern, *color));
+    }
+
+    // override any random pattern if a set one was provided
+    for (i, set_p) in options.layers.into_iter().enumerate() {
+        if let Some((pattern_id, color)) = set_p {
+            check_override(i, &patterns)?;
+            let new_pattern = match (pattern_id, color) {
+                (None, None) => patterns[i],
+                (Some(i), None) => {
+                    check_override(i, &patterns)?;
+                    (i, patterns[i].1)
+                }
+                (None, Some(c)) => (patterns[i].0, c),
+                (Some(i), Some(c)) => (i, c),
+            };
+            patterns[i] = new_pattern;
+        }
+    }
+
+    // technically you can just build your own banners via this
+    // it will still run the randomizer for all 6 layers etc
+    // and you can then just override them all and or just a few
+    // and then truncate it to the amount you want
+    if let Some(max_layers) = options.max_layers {
+        patterns.truncate(max_layers);
+    }
+
+    // important we do base_color AFTER patterns, the rng is sequential
+    let base = match options.base_color {
+        Some(color) => color,
+        None => *random_color(rng),
+    };
+
+    Ok(BannerDescription {
+        base,
+        layers: patterns,
+    })
+}
@@ -0,0 +1,87 @@
+//! Uniform over every banner: the layer count is weighted by how many banners have it,
+//! then every pattern & color is equally likely.
+//!
+//! [`Constraints`](crate::Constraints) only shrink the lists everything is picked from,
+//! with nothing constrained the draws are exactly the same as before constraints existed.
+//! A [`Profile`](crate::Profile) replaces the uniform picks with weighted ones.
+
+use super::{check_override, get_amount_of_layers};
+use crate::{BannerDescription, Combinations, GenerateOptions, GenerationError};
+use mcb::Color;
+use rand::{
+    Rng,
+    distr::{Distribution, weighted::WeightedIndex},
+    seq::IndexedRandom,
+};
+use rand_chacha::ChaCha8Rng;
+
+/// What random layers & the base are picked from, in order
+pub(super) struct Allowed {
+    pub patterns: Vec<usize>,
+    pub colors: Vec<Color>,
+    /// Picks by index into `patterns` & `colors` instead of uniformly, from a profile
+    pub weights: Option<Weights>,
+}
+
+pub(super) struct Weights {
+    pub patterns: WeightedIndex<f64>,
+    pub colors: WeightedIndex<f64>,
+    /// Picks the layer count, `None` keeps it weighted by how many banners have it
+    pub layers: Option<WeightedIndex<f64>>,
+}
+
+impl Allowed {
+    pub fn color(&self, rng: &mut ChaCha8Rng) -> Color {
+        match &self.weights {
+            Some(weights) => self.colors[weights.colors.sample(rng)],
+            None => *self.colors.choose(rng).unwrap(),
+        }
+    }
+
+    /// A random layer, the pattern is picked before the color
+    pub fn layer(&self, rng: &mut ChaCha8Rng) -> (usize, Color) {
+        let pattern = match &self.weights {
+            Some(weights) => self.patterns[weights.patterns.sample(rng)],
+            None => self.patterns[rng.random_range(0..self.patterns.len())],
+        };
+        (pattern, self.color(rng))
+    }
+}
+
+pub(super) fn generate(
+    rng: &mut ChaCha8Rng,
+    combinations: &Combinations,
+    allowed: &Allowed,
+    options: GenerateOptions,
+) -> Result<BannerDescription, GenerationError> {
+    let amount = match allowed.weights.as_ref().and_then(|w| w.layers.as_ref()) {
+        Some(layers) => layers.sample(rng),
+        None => get_amount_of_layers(rng, combinations),
+    };
+    let mut layers = (0..amount).map(|_| allowed.layer(rng)).collect::<Vec<_>>();
+
+    for (i, layer) in options.layers.into_iter().enumerate() {
+        let Some((pattern_id, color)) = layer else {
+            continue;
+        };
+        check_override(i, &layers)?;
+
+        let (random_pattern, random_color) = layers[i];
+        layers[i] = (
+            pattern_id.unwrap_or(random_pattern),
+            color.unwrap_or(random_color),
+        );
+    }
+
+    if let Some(max_layers) = options.max_layers {
+        layers.truncate(max_layers);
+    }
+
+    // the base color comes last, the rng is sequential
+    let base = match options.base_color {
+        Some(color) => color,
+        None => allowed.color(rng),
+    };
+
+    Ok(BannerDescription { base, layers })
+}
@@ -1,125 +1,813 @@
 use axum::{
     Json,
-    extract::{Path, State},
-    http::{StatusCode, header},
-    response::IntoResponse,
+    body::Bytes,
+    extract::{Path, RawQuery, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
 };
 use axum_extra::extract::Query;
-use image::{ImageFormat, imageops::FilterType};
+use image::{ImageFormat, ImageReader, Limits, imageops::FilterType};
 use mcb::*;
-use serde_json::json;
 use std::{
     io::{BufWriter, Cursor},
-    sync::Arc,
+    sync::{Arc, atomic::Ordering},
+    time::Instant,
+};
+use tracing::Span;
+
+use minecraft_banners::{
+    BannerDescription, Constraints, GenerateOptions, GenerationError, GeneratorVersion, Image,
+    ParseStyleError, Seed, SolveOptions, Style, TextError, VersionedSeed, analyze, blazon,
+    parse_blazon, parse_seed, similar, text,
 };
 
 use crate::{
-    AppState, banner_from_pattern_list,
-    generation::{
-        generate_pattern_list, generate_seed, get_possible_combinations, get_rng_from_seed,
+    AppState,
+    caching::{
+        CACHE_IMMUTABLE, CACHE_NO_STORE, CACHE_UNPINNED, banner_etag, is_not_modified,
+        not_modified, text_etag,
+    },
+    logging::millis,
+    profiles::NamedProfile,
+    query::{
+        CompareQuery, GetBannerQuery, SimilarQuery, SolveQuery, TextQuery, map_base_color,
+        map_layers, parse_banner, parse_color, parse_constraints,
+    },
+    render_cache::BannerKey,
+    responses::{
+        AnalyzeResponse, BlazonResponse, CanonicalResponse, ColorCoverage, CompareResponse,
+        LabColor, LayerAnalysis, LetterResponse, MetadataResponse, PatternListResponse,
+        ReadyResponse, SimilarBanner, SimilarResponse, SolveResponse, TextResponse,
     },
-    increment_banner_count, map_base_color,
-    query::{GetBannerQuery, map_layers},
-    random_color,
 };
 
-// add a ton of cache headers
+/// The only format banners are encoded as (for now)
+const IMAGE_FORMAT: &str = "webp";
+/// Widest & tallest image `/solve` accepts, it all gets scaled down to `20x40` anyway
+const MAX_UPLOAD_SIZE: u32 = 4096;
+/// Most characters `/text` writes at once
+const MAX_TEXT_LENGTH: usize = 32;
+/// Upper bound for `beam_width`, solving time grows linearly with it
+const MAX_BEAM_WIDTH: usize = 16;
+/// Most banners `/similar` returns
+const MAX_SIMILAR: usize = 50;
+/// How many colors `/analyze` counts as dominant
+const DOMINANT_COLORS: usize = 3;
+/// Which seed a banner/pattern list was generated from
+pub const X_BANNER_SEED: HeaderName = HeaderName::from_static("x-banner-seed");
+/// Which [`GeneratorVersion`] turned the seed into a banner
+pub const X_BANNER_VERSION: HeaderName = HeaderName::from_static("x-banner-version");
+
+#[utoipa::path(
+    get,
+    path = "/banner",
+    tag = "banners",
+    description = "Generate a random banner with a new random seed, never cached.",
+    params(GetBannerQuery),
+    responses(
+        (status = 200, description = "The banner", content_type = "image/webp", body = [u8],
+            headers(
+                ("x-banner-seed" = String, description = "The seed that was used"),
+                ("x-banner-version" = String, description = "The generator version that was used"),
+                ("location" = String, description = "The permanent `/banner/{seed}` url, pinned to its version"),
+            )),
+        (status = 400, description = "Invalid query arguments", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Generation failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_random_banner(
+    RawQuery(raw_query): RawQuery,
+    Query(query): Query<GetBannerQuery>,
+    request_headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, (StatusCode, String)> {
+    banner_response(None, raw_query, query, &request_headers, &state).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/banner/{seed}",
+    tag = "banners",
+    description = "Generate a banner from a seed, with a strong `ETag`. Cached as `immutable` when the version is pinned (`v2-123` or `?v=2`).",
+    params(
+        ("seed" = String, Path, description = "A seed from `/seed`, optionally pinned to a version like `v2-123`. Anything invalid counts as a random one"),
+        GetBannerQuery,
+    ),
+    responses(
+        (status = 200, description = "The banner", content_type = "image/webp", body = [u8],
+            headers(
+                ("x-banner-seed" = String, description = "The seed that was used"),
+                ("x-banner-version" = String, description = "The generator version that was used"),
+                ("etag" = String),
+            )),
+        (status = 304, description = "Matched `If-None-Match`"),
+        (status = 400, description = "Invalid query arguments", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Generation failed", content_type = "text/plain", body = String),
+    )
+)]
 pub async fn get_banner(
-    seed: Option<Path<String>>,
+    Path(seed): Path<String>,
+    RawQuery(raw_query): RawQuery,
     Query(query): Query<GetBannerQuery>,
+    request_headers: HeaderMap,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    let mut rng = match get_rng_from_seed(seed, state.patterns.len()) {
-        Ok(rng) => rng,
-        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#?}"))),
+) -> Result<Response, (StatusCode, String)> {
+    banner_response(
+        parse_seed(&seed),
+        raw_query,
+        query,
+        &request_headers,
+        &state,
+    )
+    .await
+}
+
+// seeded banners are immutable (once pinned to a version), random ones point to their seeded url
+async fn banner_response(
+    seeded: Option<VersionedSeed>,
+    raw_query: Option<String>,
+    query: GetBannerQuery,
+    request_headers: &HeaderMap,
+    state: &Arc<AppState>,
+) -> Result<Response, (StatusCode, String)> {
+    check_output_size(query.width, 1, state.config.max_output_pixels)?;
+    let version = resolve_version(seeded, query.v.as_deref(), state)?;
+    let profile = query_profile(&query, state)?;
+    // profiles come from the config, so they can change just like the default version
+    let pinned =
+        (seeded.is_some_and(|s| s.version.is_some()) || query.v.is_some()) && profile.is_none();
+    // the default version can change, so only pinned urls always mean the same banner
+    let cache_control = if pinned {
+        CACHE_IMMUTABLE
+    } else {
+        CACHE_UNPINNED
     };
+    let seeded = seeded.map(|s| s.seed);
 
-    let base_color = map_base_color(query.base_color);
-    let layers = map_layers(query.layers);
+    let etag = banner_etag(
+        state.registry_version,
+        Some(version),
+        seeded,
+        &query,
+        profile.map(|p| p.fingerprint),
+        IMAGE_FORMAT,
+    );
+    if seeded.is_some() && is_not_modified(request_headers, &etag) {
+        return Ok(not_modified(etag, cache_control));
+    }
 
-    let pattern_list =
-        match generate_pattern_list(&mut rng, &state.patterns, layers, query.max_layers) {
-            Ok(i) => i,
-            Err(e) => {
-                return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#?}")));
-            }
-        };
-    let mut img = banner_from_pattern_list(
-        &mut rng,
-        &mut state.base.clone(),
-        base_color,
-        pattern_list,
-        &state.patterns,
-    )
-    .unwrap();
+    let (seed, banner) = generate_banner(state, version, seeded, &query).await?;
 
-    increment_banner_count(&state.banner_count).await;
+    let span = Span::current();
+    span.record("seed", seed);
+    span.record("version", tracing::field::display(version));
+    span.record("layers", tracing::field::debug(&banner.layers));
 
-    if let Some(width) = query.width {
-        img = image::imageops::resize(&img, width, width * 2, FilterType::Nearest);
+    let key = BannerKey::new(banner, query.width, IMAGE_FORMAT);
+    let bytes = render_cached(state, key)?;
+    state.banner_count.increment();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("image/webp"));
+    headers.insert(X_BANNER_SEED, seed_header(seed));
+    headers.insert(X_BANNER_VERSION, version_header(version));
+    if seeded.is_some() {
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(cache_control),
+        );
+        headers.insert(header::ETAG, etag);
+    } else {
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_NO_STORE),
+        );
+        let seed = VersionedSeed::pinned(version, seed);
+        headers.insert(header::LOCATION, seed_location(seed, raw_query));
     }
 
-    let mut buf = BufWriter::new(Cursor::new(vec![]));
-    img.write_to(&mut buf, ImageFormat::WebP).unwrap();
+    Ok((headers, bytes).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/pattern",
+    tag = "patterns",
+    description = "Generate a list of random patterns with a new random seed.",
+    params(GetBannerQuery),
+    responses(
+        (status = 200, description = "The patterns", body = PatternListResponse,
+            headers(
+                ("x-banner-seed" = String, description = "The seed that was used"),
+                ("x-banner-version" = String, description = "The generator version that was used"),
+            )),
+        (status = 400, description = "Invalid layers", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Generation failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_random_pattern_list(
+    Query(query): Query<GetBannerQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    pattern_list_response(None, query, &state).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/pattern/{seed}",
+    tag = "patterns",
+    description = "Generate a list of patterns from a seed.",
+    params(
+        ("seed" = String, Path, description = "A seed from `/seed`, optionally pinned to a version like `v2-123`. Anything invalid counts as a random one"),
+        GetBannerQuery,
+    ),
+    responses(
+        (status = 200, description = "The patterns", body = PatternListResponse,
+            headers(
+                ("x-banner-seed" = String, description = "The seed that was used"),
+                ("x-banner-version" = String, description = "The generator version that was used"),
+            )),
+        (status = 400, description = "Invalid layers", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Generation failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_pattern_list(
+    Path(seed): Path<String>,
+    Query(query): Query<GetBannerQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    pattern_list_response(parse_seed(&seed), query, &state).await
+}
+
+async fn pattern_list_response(
+    seed: Option<VersionedSeed>,
+    query: GetBannerQuery,
+    state: &Arc<AppState>,
+) -> Result<impl IntoResponse + use<>, (StatusCode, String)> {
+    let version = resolve_version(seed, query.v.as_deref(), state)?;
+    let (seed, banner) = generate_banner(state, version, seed.map(|s| s.seed), &query).await?;
+
+    let span = Span::current();
+    span.record("seed", seed);
+    span.record("version", tracing::field::display(version));
+    span.record("layers", tracing::field::debug(&banner.layers));
+
+    let patterns = named_layers(&banner.layers, state);
+
+    let body = Json(PatternListResponse {
+        seed: seed.to_string(),
+        version: version.to_string(),
+        base: banner.base.to_string(),
+        patterns,
+    });
 
-    let bytes = buf.into_inner().unwrap().into_inner();
     let headers = [
-        (header::CONTENT_TYPE, "image/webp"),
-        (header::CACHE_CONTROL, "public, max-age=3600"),
+        (X_BANNER_SEED, seed_header(seed)),
+        (X_BANNER_VERSION, version_header(version)),
     ];
+    Ok((headers, body))
+}
 
-    Ok((headers, bytes))
+/// Redirects to the permanent url of a new random banner, keeping any query arguments
+#[utoipa::path(
+    get,
+    path = "/random",
+    tag = "banners",
+    description = "Redirects to `/banner/{seed}` with a new random seed, keeping any query arguments.",
+    params(GetBannerQuery),
+    responses(
+        (status = 302, description = "Redirect to the banner",
+            headers(
+                ("location" = String, description = "The permanent `/banner/{seed}` url, pinned to its version"),
+                ("x-banner-seed" = String, description = "The seed that was picked"),
+                ("x-banner-version" = String, description = "The generator version it's pinned to"),
+            )),
+        (status = 400, description = "Invalid `v`", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Too many patterns to count", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_random(
+    RawQuery(raw_query): RawQuery,
+    Query(query): Query<GetBannerQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let version = resolve_version(None, query.v.as_deref(), &state)?;
+    let constraints = query_constraints(&query, &state)?;
+    // checked up front, the seeded url it redirects to keeps the style
+    query_style(&query)?;
+    let profile = query_profile(&query, &state)?;
+    let seed = state
+        .generator(version)
+        .constrained(&constraints)
+        .weighted(profile.map(|p| &p.profile))
+        .random_seed()
+        .map_err(generation_error)?;
+
+    Ok((
+        StatusCode::FOUND,
+        [
+            (
+                header::LOCATION,
+                seed_location(VersionedSeed::pinned(version, seed), raw_query),
+            ),
+            (
+                header::CACHE_CONTROL,
+                HeaderValue::from_static(CACHE_NO_STORE),
+            ),
+            (X_BANNER_SEED, seed_header(seed)),
+            (X_BANNER_VERSION, version_header(version)),
+        ],
+    ))
 }
 
-pub async fn get_pattern_list(
-    seed: Option<Path<String>>,
+#[utoipa::path(
+    get,
+    path = "/seed",
+    tag = "patterns",
+    description = "Generate a new seed within the determined range.",
+    responses(
+        (status = 200, description = "The seed", content_type = "text/plain", body = String, example = "1234567890"),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Too many patterns to count", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_new_seed(
+    State(state): State<Arc<AppState>>,
+) -> Result<String, (StatusCode, String)> {
+    let seed = state
+        .generator(state.config.generator_version)
+        .random_seed()
+        .map_err(generation_error)?;
+    Ok(seed.to_string())
+}
+
+#[utoipa::path(
+    get,
+    path = "/compare",
+    tag = "patterns",
+    description = "Checks if two banners render to the exact same pixels, returning both without the layers that don't show.",
+    params(CompareQuery),
+    responses(
+        (status = 200, description = "The comparison", body = CompareResponse),
+        (status = 400, description = "Invalid banners or seeds", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Rendering failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_compare(
+    Query(query): Query<CompareQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CompareResponse>, (StatusCode, String)> {
+    let a = query_banner(&query.a, "a", &state)?;
+    let b = query_banner(&query.b, "b", &state)?;
+
+    let identical = state
+        .registry
+        .identical(&a, &b)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#?}")))?;
+
+    Ok(Json(CompareResponse {
+        identical,
+        a: canonical_response(&a, &state)?,
+        b: canonical_response(&b, &state)?,
+    }))
+}
+
+/// A banner from a seed or written out, see [`parse_banner`]
+fn query_banner(
+    banner: &str,
+    name: &str,
+    state: &AppState,
+) -> Result<BannerDescription, (StatusCode, String)> {
+    let invalid = || (StatusCode::BAD_REQUEST, format!("Invalid '{name}'"));
+
+    if banner.contains('[') {
+        let banner = parse_banner(banner).ok_or_else(invalid)?;
+        if banner.layers.len() > Banner::VANILLA_MAX_PATTERN_SIZE {
+            return Err(generation_error(GenerationError::TooManyLayers));
+        }
+        state
+            .registry
+            .check_layers(&banner.layers)
+            .map_err(generation_error)?;
+        return Ok(banner);
+    }
+
+    let seed = parse_seed(banner).ok_or_else(invalid)?;
+    let version = resolve_version(Some(seed), None, state)?;
+    let (_, banner) = state
+        .generator(version)
+        .generate(Some(seed.seed), GenerateOptions::default())
+        .map_err(generation_error)?;
+    Ok(banner)
+}
+
+fn canonical_response(
+    banner: &BannerDescription,
+    state: &AppState,
+) -> Result<CanonicalResponse, (StatusCode, String)> {
+    let canonical = state
+        .registry
+        .canonicalize(banner)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#?}")))?;
+
+    let patterns = named_layers(&canonical.layers, state);
+
+    Ok(CanonicalResponse {
+        base: canonical.base.to_string(),
+        patterns,
+        hidden: banner.layers.len() - canonical.layers.len(),
+        preview: create_url(&canonical),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/analyze/{seed}",
+    tag = "patterns",
+    description = "The colors of a seeded banner: how much each dye covers, the dominant ones, the average color & how much every layer stands out.",
+    params(
+        ("seed" = String, Path, description = "A seed from `/seed`, optionally pinned to a version like `v2-123`"),
+        GetBannerQuery,
+    ),
+    responses(
+        (status = 200, description = "The analysis", body = AnalyzeResponse),
+        (status = 400, description = "Invalid seed or query arguments", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Rendering failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_analyze(
+    Path(seed): Path<String>,
     Query(query): Query<GetBannerQuery>,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    let mut rng = match get_rng_from_seed(seed, state.patterns.len()) {
-        Ok(rng) => rng,
-        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#?}"))),
-    };
+) -> Result<Json<AnalyzeResponse>, (StatusCode, String)> {
+    let seed = parse_seed(&seed).ok_or((StatusCode::BAD_REQUEST, "Invalid seed".to_string()))?;
+    let version = resolve_version(Some(seed), query.v.as_deref(), &state)?;
+    let (seed, banner) = generate_banner(&state, version, Some(seed.seed), &query).await?;
 
-    let base_color = map_base_color(query.base_color);
+    let palette = analyze(&state.registry, &banner)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
 
-    let layers = map_layers(query.layers);
+    let dominant = palette.dominant();
+    let layers = banner
+        .layers
+        .iter()
+        .zip(&palette.layers)
+        .map(|((pattern_id, _), layer)| LayerAnalysis {
+            pattern: state.registry.name(*pattern_id).unwrap().to_owned(),
+            color: layer.color.to_string(),
+            below: layer.below.to_string(),
+            contrast: layer.contrast,
+        })
+        .collect();
 
-    let pattern_list =
-        match generate_pattern_list(&mut rng, &state.patterns, layers, query.max_layers) {
-            Ok(i) => i,
-            Err(e) => {
-                return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#?}")));
-            }
-        };
-    let pattern_list = pattern_list
+    Ok(Json(AnalyzeResponse {
+        seed: VersionedSeed::pinned(version, seed).to_string(),
+        dominant: dominant
+            .iter()
+            .take(DOMINANT_COLORS)
+            .map(|(color, _)| color.to_string())
+            .collect(),
+        coverage: dominant
+            .into_iter()
+            .map(|(color, coverage)| ColorCoverage {
+                color: color.name().to_owned(),
+                hex: color.to_string(),
+                coverage,
+            })
+            .collect(),
+        average_lab: LabColor {
+            l: palette.average.l,
+            a: palette.average.a,
+            b: palette.average.b,
+        },
+        layers,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/blazon/{seed}",
+    tag = "patterns",
+    description = "A seeded banner written out in heraldry, `/create?blazon=` reads it back.",
+    params(
+        ("seed" = String, Path, description = "A seed from `/seed`, optionally pinned to a version like `v2-123`"),
+        GetBannerQuery,
+    ),
+    responses(
+        (status = 200, description = "The blazon", body = BlazonResponse),
+        (status = 400, description = "Invalid seed or query arguments", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Generation failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_blazon(
+    Path(seed): Path<String>,
+    Query(query): Query<GetBannerQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<BlazonResponse>, (StatusCode, String)> {
+    let seed = parse_seed(&seed).ok_or((StatusCode::BAD_REQUEST, "Invalid seed".to_string()))?;
+    let version = resolve_version(Some(seed), query.v.as_deref(), &state)?;
+    let (seed, banner) = generate_banner(&state, version, Some(seed.seed), &query).await?;
+
+    Ok(Json(BlazonResponse {
+        seed: VersionedSeed::pinned(version, seed).to_string(),
+        blazon: blazon(&state.registry, &banner).map_err(generation_error)?,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/similar/{seed}",
+    tag = "patterns",
+    description = "Banners that look like the one of a seed: the same banner with one thing changed or other seeds, closest first.",
+    params(
+        ("seed" = String, Path, description = "A seed from `/seed`, optionally pinned to a version like `v2-123`"),
+        SimilarQuery,
+    ),
+    responses(
+        (status = 200, description = "The similar banners", body = SimilarResponse),
+        (status = 400, description = "Invalid seed or query arguments", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Searching failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_similar(
+    Path(seed): Path<String>,
+    Query(query): Query<SimilarQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SimilarResponse>, (StatusCode, String)> {
+    let seed = parse_seed(&seed).ok_or((StatusCode::BAD_REQUEST, "Invalid seed".to_string()))?;
+    let version = resolve_version(Some(seed), query.v.as_deref(), &state)?;
+    let count = query.count.unwrap_or(10);
+    if count == 0 || count > MAX_SIMILAR {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("'count' must be between 1 and {MAX_SIMILAR}"),
+        ));
+    }
+    // checks the seed range before doing any real work
+    state
+        .generator(version)
+        .generate(Some(seed.seed), GenerateOptions::default())
+        .map_err(generation_error)?;
+
+    // renders a few hundred banners, too long to hold up an async worker
+    let start = Instant::now();
+    let searching = state.clone();
+    let neighbors = tokio::task::spawn_blocking(move || {
+        similar(&searching.generator(version), seed.seed, count)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    Span::current().record("render_ms", millis(start));
+
+    let similar = neighbors
         .into_iter()
-        .map(|(pattern_id, color)| {
-            let pattern = state.patterns[pattern_id].0.to_owned();
-            (pattern, color.to_string())
+        .map(|neighbor| {
+            let seed = VersionedSeed::pinned(version, neighbor.seed);
+            SimilarBanner {
+                seed: seed.to_string(),
+                url: seed_url(seed, &neighbor.options),
+                distance: neighbor.distance,
+                base: neighbor.banner.base.to_string(),
+                patterns: named_layers(&neighbor.banner.layers, &state),
+            }
         })
-        .collect::<Vec<(String, String)>>();
+        .collect();
 
-    // important we do base_color AFTER patterns, the same as in get_banner
-    // so the seed is the same since its seq
-    let base_color = match base_color {
-        Some(color) => color,
-        None => *random_color(&mut rng),
+    Ok(Json(SimilarResponse {
+        seed: VersionedSeed::pinned(version, seed.seed).to_string(),
+        similar,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/solve",
+    tag = "patterns",
+    description = "Finds the banner that looks the most like the uploaded image. Any format & size works, it gets stretched to `20x40`.",
+    params(SolveQuery),
+    request_body(content = [u8], description = "The image", content_type = "image/*"),
+    responses(
+        (status = 200, description = "The closest banner", body = SolveResponse),
+        (status = 400, description = "Not an image or invalid query arguments", content_type = "text/plain", body = String),
+        (status = 413, description = "The image is over 2 MB", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Solving failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn solve_banner(
+    Query(query): Query<SolveQuery>,
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Json<SolveResponse>, (StatusCode, String)> {
+    let options = SolveOptions {
+        max_layers: query.max_layers.unwrap_or(Banner::VANILLA_MAX_PATTERN_SIZE),
+        beam_width: query
+            .beam_width
+            .unwrap_or(SolveOptions::default().beam_width),
     };
+    if options.max_layers > Banner::VANILLA_MAX_PATTERN_SIZE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            GenerationError::TooManyLayers.to_string(),
+        ));
+    }
+    if options.beam_width == 0 || options.beam_width > MAX_BEAM_WIDTH {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("'beam_width' must be between 1 and {MAX_BEAM_WIDTH}"),
+        ));
+    }
 
-    Ok(Json(json!({
-        "base": base_color.to_string(),
-        "patterns": pattern_list
-    })))
+    let target = decode_upload(&body)?;
+
+    // takes a few hundred ms, too long to hold up an async worker
+    let start = Instant::now();
+    let solving = state.clone();
+    let solution =
+        tokio::task::spawn_blocking(move || anyhow::Ok(solving.solver()?.solve(&target, options)))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+
+    let banner = solution.banner;
+    let span = Span::current();
+    span.record("render_ms", millis(start));
+    span.record("layers", tracing::field::debug(&banner.layers));
+
+    let patterns = named_layers(&banner.layers, &state);
+
+    Ok(Json(SolveResponse {
+        base: banner.base.to_string(),
+        patterns,
+        error: solution.error,
+        preview: create_url(&banner),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/text",
+    tag = "banners",
+    description = "Renders a text as a row of banners, one per character, cached as `immutable` with a strong `ETag`.",
+    params(TextQuery),
+    responses(
+        (status = 200, description = "The banners", content_type = "image/webp", body = [u8],
+            headers(("etag" = String))),
+        (status = 304, description = "Matched `If-None-Match`"),
+        (status = 400, description = "Unsupported characters or invalid query arguments", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "A pattern of the letters isn't loaded or rendering failed", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_text(
+    Query(query): Query<TextQuery>,
+    request_headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, (StatusCode, String)> {
+    let etag = text_etag(state.registry_version, &query, IMAGE_FORMAT);
+    if is_not_modified(&request_headers, &etag) {
+        return Ok(not_modified(etag, CACHE_IMMUTABLE));
+    }
+
+    let banners = text_banners(&query, &state)?;
+    check_output_size(
+        query.width,
+        banners.len() as u64,
+        state.config.max_output_pixels,
+    )?;
+
+    let start = Instant::now();
+    let mut img = state
+        .registry
+        .render_row(&banners)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#?}")))?;
+    state.metrics.render_generation.observe(start.elapsed());
+    Span::current().record("render_ms", millis(start));
+
+    let start = Instant::now();
+    if let Some(width) = query.width {
+        let (width, height) = output_size(width, banners.len())?;
+        img = image::imageops::resize(&img, width, height, FilterType::Nearest);
+    }
+    let bytes = encode_webp(&img)?;
+    state.metrics.render_encoding.observe(start.elapsed());
+    Span::current().record("encode_ms", millis(start));
+
+    let headers = [
+        (header::CONTENT_TYPE, HeaderValue::from_static("image/webp")),
+        (
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_IMMUTABLE),
+        ),
+        (header::ETAG, etag),
+    ];
+
+    Ok((headers, bytes).into_response())
 }
 
-pub async fn get_new_seed(State(state): State<Arc<AppState>>) -> String {
-    generate_seed(state.patterns.len()).to_string()
+#[utoipa::path(
+    get,
+    path = "/text/recipes",
+    tag = "patterns",
+    description = "The banner recipe for every character of a text, what `/text` renders.",
+    params(TextQuery),
+    responses(
+        (status = 200, description = "The banners", body = TextResponse),
+        (status = 400, description = "Unsupported characters or invalid query arguments", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "A pattern of the letters isn't loaded", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_text_recipes(
+    Query(query): Query<TextQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<TextResponse>, (StatusCode, String)> {
+    let banners = text_banners(&query, &state)?;
+
+    let letters = query
+        .value
+        .chars()
+        .zip(banners)
+        .map(|(character, banner)| {
+            let patterns = named_layers(&banner.layers, &state);
+
+            LetterResponse {
+                character: character.to_string(),
+                base: banner.base.to_string(),
+                patterns,
+                preview: create_url(&banner),
+            }
+        })
+        .collect();
+
+    Ok(Json(TextResponse { letters }))
 }
 
-pub async fn get_metadata(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+/// The banners spelling out `query.value`
+fn text_banners(
+    query: &TextQuery,
+    state: &AppState,
+) -> Result<Vec<BannerDescription>, (StatusCode, String)> {
+    let length = query.value.chars().count();
+    if length == 0 || length > MAX_TEXT_LENGTH {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("'value' must be between 1 and {MAX_TEXT_LENGTH} characters"),
+        ));
+    }
+
+    let color = |color: Option<&str>, default: Color, name: &str| match color {
+        Some(color) => {
+            parse_color(color).ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Invalid '{name}'")))
+        }
+        None => Ok(default),
+    };
+    let foreground = color(query.fg.as_deref(), Color::Black, "fg")?;
+    let background = color(query.bg.as_deref(), Color::White, "bg")?;
+
+    let banners =
+        text::banners(&state.registry, &query.value, foreground, background).map_err(|e| {
+            let status = match e {
+                TextError::UnknownCharacter(_) => StatusCode::BAD_REQUEST,
+                TextError::MissingPattern(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string())
+        })?;
+
+    Span::current().record(
+        "layers",
+        tracing::field::debug(banners.iter().map(|b| &b.layers).collect::<Vec<_>>()),
+    );
+    Ok(banners)
+}
+
+#[utoipa::path(
+    get,
+    path = "/metadata",
+    tag = "patterns",
+    description = "Every available pattern, every color & how many combinations are possible. `distinct_combinations` is only there once it's been precomputed for these patterns.",
+    responses(
+        (status = 200, description = "The metadata", body = MetadataResponse),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Too many patterns to count", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_metadata(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<MetadataResponse>, (StatusCode, String)> {
     let patterns = state
-        .patterns
+        .registry
+        .patterns()
         .iter()
         .map(|p| p.0.to_owned())
         .collect::<Vec<String>>();
@@ -128,44 +816,171 @@ pub async fn get_metadata(State(state): State<Arc<AppState>>) -> impl IntoRespon
         .map(|c| c.to_string())
         .collect::<Vec<String>>();
 
-    Json(json!({
-        "patterns": patterns,
-        "colors": colors,
-        "combinations": get_possible_combinations(state.patterns.len()).to_string()
+    let mut profiles = state.profiles.keys().cloned().collect::<Vec<_>>();
+    profiles.sort_unstable();
+
+    // the same for every version
+    let combinations = state
+        .generator(state.config.generator_version)
+        .combinations()
+        .map_err(generation_error)?;
+
+    Ok(Json(MetadataResponse {
+        patterns,
+        colors,
+        combinations: combinations.total.to_string(),
+        combinations_per_layer: combinations
+            .per_layer
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+        distinct_combinations: state.distinct.as_ref().map(|d| d.total.to_string()),
+        distinct_combinations_per_layer: state
+            .distinct
+            .as_ref()
+            .map(|d| d.per_layer.iter().map(|c| c.to_string()).collect()),
+        profiles,
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "server",
+    description = "Basis string to check if the server is online.",
+    responses((status = 200, content_type = "text/plain", body = String)),
+)]
+pub async fn get_root() -> &'static str {
+    "Every place you've ever imagined, it's real"
+}
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "server",
+    description = "Liveness check, always `200` while the process is up.",
+    responses((status = 200, content_type = "text/plain", body = String, example = "ok")),
+)]
+pub async fn get_health() -> &'static str {
+    "ok"
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "server",
+    description = "Readiness check, fails if no patterns could be loaded or the server is shutting down.",
+    responses(
+        (status = 200, description = "Ready", body = ReadyResponse),
+        (status = 503, description = "Not ready", body = ReadyResponse),
+    )
+)]
+pub async fn get_ready(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let ready = state.ready.load(Ordering::Relaxed);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(ReadyResponse {
+            ready,
+            patterns: state.registry.len(),
+        }),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/create",
+    tag = "banners",
+    description = "Create a custom banner without any randomness (from `base_color` & `layers` or a `blazon`), cached as `immutable` with a strong `ETag`.",
+    params(GetBannerQuery),
+    responses(
+        (status = 200, description = "The banner", content_type = "image/webp", body = [u8],
+            headers(("etag" = String))),
+        (status = 304, description = "Matched `If-None-Match`"),
+        (status = 400, description = "Missing `base_color`, invalid layers, an invalid `blazon` or a `blazon` with `base_color`/`layers`", content_type = "text/plain", body = String),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+        (status = 500, description = "Rendering failed", content_type = "text/plain", body = String),
+    )
+)]
 pub async fn create_banner(
     Query(query): Query<GetBannerQuery>,
+    request_headers: HeaderMap,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    let base_color = match query.base_color {
+) -> Result<Response, (StatusCode, String)> {
+    if query.blazon.is_some() && (query.base_color.is_some() || !query.layers.is_empty()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "'blazon' replaces 'base_color' & 'layers', send one or the other".to_string(),
+        ));
+    }
+
+    // no randomness at all, so it's just as cacheable as a seeded banner
+    let etag = banner_etag(
+        state.registry_version,
+        None,
+        None,
+        &query,
+        None,
+        IMAGE_FORMAT,
+    );
+    if is_not_modified(&request_headers, &etag) {
+        return Ok(not_modified(etag, CACHE_IMMUTABLE));
+    }
+
+    check_output_size(query.width, 1, state.config.max_output_pixels)?;
+    let banner = match query.blazon {
+        Some(blazon) => parse_blazon(&state.registry, &blazon)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?,
+        None => query_layers(query.base_color, query.layers, &state)?,
+    };
+
+    Span::current().record("layers", tracing::field::debug(&banner.layers));
+
+    let key = BannerKey::new(banner, query.width, IMAGE_FORMAT);
+    let bytes = render_cached(&state, key)?;
+    state.banner_count.increment();
+
+    let headers = [
+        (header::CONTENT_TYPE, HeaderValue::from_static("image/webp")),
+        (
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_IMMUTABLE),
+        ),
+        (header::ETAG, etag),
+    ];
+
+    Ok((headers, bytes).into_response())
+}
+
+/// The banner `/create` builds out of `base_color` & `layers`, every layer needs both halves
+fn query_layers(
+    base_color: Option<u8>,
+    layers: Vec<Option<String>>,
+    state: &AppState,
+) -> Result<BannerDescription, (StatusCode, String)> {
+    let base_color = match base_color {
         Some(color) => match Color::from_repr(color) {
             Some(c) => c,
             None => return Err((StatusCode::BAD_REQUEST, "Invalid 'base_color'".to_string())),
         },
         None => return Err((StatusCode::BAD_REQUEST, "Missing 'base_color'".to_string())),
     };
-    let layers = map_layers(query.layers);
-
-    let mut banner = match Banner::new(&mut state.base.clone(), base_color) {
-        Ok(b) => b,
-        Err(_) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to create banner".to_string(),
-            ));
-        }
-    };
+    let layers = map_layers(layers);
 
+    let mut pattern_list = Vec::with_capacity(layers.len());
     for layer in layers {
         let layer = match layer {
             Some(l) => l,
             None => continue,
         };
 
-        let (pattern_id, color) = match layer {
-            (Some(i), Some(c)) => (i, c),
+        match layer {
+            (Some(i), Some(c)) => pattern_list.push((i, c)),
             _ => {
                 return Err((
                     StatusCode::BAD_REQUEST,
@@ -173,34 +988,387 @@ pub async fn create_banner(
                 ));
             }
         };
-        let pattern = state.patterns[pattern_id].1.clone();
-        let pattern = Pattern::new(pattern);
-        match banner.add_pattern(pattern, &color) {
-            Ok(_) => (),
-            Err(_) => {
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to add layer".to_string(),
-                ));
-            }
-        };
     }
+    state
+        .registry
+        .check_layers(&pattern_list)
+        .map_err(generation_error)?;
 
-    increment_banner_count(&state.banner_count).await;
+    Ok(BannerDescription {
+        base: base_color,
+        layers: pattern_list,
+    })
+}
 
-    let mut img = banner.img_owned();
-    if let Some(width) = query.width {
-        img = image::imageops::resize(&img, width, width * 2, FilterType::Nearest);
+/// Returns the encoded banner from the [`RenderCache`](crate::render_cache::RenderCache),
+/// rendering & caching it first if it isn't in there.
+fn render_cached(state: &AppState, key: BannerKey) -> Result<Bytes, (StatusCode, String)> {
+    if let Some(bytes) = state.render_cache.get(&key) {
+        return Ok(bytes);
+    }
+
+    let start = Instant::now();
+    let mut img = match state.registry.render(&key.banner) {
+        Ok(img) => img,
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#?}"))),
+    };
+    state.metrics.render_generation.observe(start.elapsed());
+    Span::current().record("render_ms", millis(start));
+
+    let start = Instant::now();
+    if let Some(width) = key.width {
+        let (width, height) = output_size(width, 1)?;
+        img = image::imageops::resize(&img, width, height, FilterType::Nearest);
     }
 
+    let bytes = encode_webp(&img)?;
+    state.metrics.render_encoding.observe(start.elapsed());
+    Span::current().record("encode_ms", millis(start));
+
+    state.render_cache.insert(key, bytes.clone());
+    Ok(bytes)
+}
+
+fn encode_webp(img: &Image) -> Result<Bytes, (StatusCode, String)> {
+    let internal = |e: String| (StatusCode::INTERNAL_SERVER_ERROR, e);
+
     let mut buf = BufWriter::new(Cursor::new(vec![]));
-    img.write_to(&mut buf, ImageFormat::WebP).unwrap();
+    img.write_to(&mut buf, ImageFormat::WebP)
+        .map_err(|e| internal(format!("Failed to encode the image: {e}")))?;
+    let buf = buf.into_inner().map_err(|e| internal(e.to_string()))?;
+    Ok(Bytes::from(buf.into_inner()))
+}
 
-    let bytes = buf.into_inner().unwrap().into_inner();
-    let headers = [
-        (header::CONTENT_TYPE, "image/webp"),
-        (header::CACHE_CONTROL, "public, max-age=3600"),
-    ];
+/// Decodes an uploaded image, refusing anything that would decode into something huge
+fn decode_upload(bytes: &[u8]) -> Result<Image, (StatusCode, String)> {
+    let bad_request =
+        |e: image::ImageError| (StatusCode::BAD_REQUEST, format!("Invalid image: {e}"));
+
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid image: {e}")))?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_UPLOAD_SIZE);
+    limits.max_image_height = Some(MAX_UPLOAD_SIZE);
+    reader.limits(limits);
+
+    Ok(reader.decode().map_err(bad_request)?.to_rgba8())
+}
+
+/// `[pattern id, hex color]` for every layer, how layers show up in JSON
+fn named_layers(layers: &[(usize, Color)], state: &AppState) -> Vec<(String, String)> {
+    layers
+        .iter()
+        .map(|(pattern_id, color)| {
+            let pattern = state.registry.name(*pattern_id).unwrap().to_owned();
+            (pattern, color.to_string())
+        })
+        .collect()
+}
+
+/// The `/create` url that renders `banner`
+fn create_url(banner: &BannerDescription) -> String {
+    let mut url = format!("/create?base_color={}", banner.base as u8);
+    for (pattern_id, color) in &banner.layers {
+        url.push_str(&format!("&layers=[{pattern_id},{}]", *color as u8));
+    }
+    url
+}
+
+/// The `/banner/{seed}` url that renders `seed` with `options`, the opposite of [`map_layers`]
+fn seed_url(seed: VersionedSeed, options: &GenerateOptions) -> String {
+    let mut query = Vec::new();
+    if let Some(base_color) = options.base_color {
+        query.push(format!("base_color={}", base_color as u8));
+    }
+    for layer in &options.layers {
+        let entry = match layer {
+            Some((pattern_id, color)) => format!(
+                "[{},{}]",
+                pattern_id.map(|p| p.to_string()).unwrap_or_default(),
+                color.map(|c| (c as u8).to_string()).unwrap_or_default()
+            ),
+            None => String::new(),
+        };
+        query.push(format!("layers={entry}"));
+    }
+    if let Some(max_layers) = options.max_layers {
+        query.push(format!("max_layers={max_layers}"));
+    }
+
+    if query.is_empty() {
+        format!("/banner/{seed}")
+    } else {
+        format!("/banner/{seed}?{}", query.join("&"))
+    }
+}
+
+fn seed_header(seed: Seed) -> HeaderValue {
+    HeaderValue::try_from(seed.to_string()).unwrap()
+}
+
+fn version_header(version: GeneratorVersion) -> HeaderValue {
+    HeaderValue::try_from(version.to_string()).unwrap()
+}
+
+/// Generates the banner for `seed` with every generation argument of `query`
+/// (constraints, style, profile & overrides).
+///
+/// Pretty banners get rendered over & over while their layers are redrawn,
+/// too long to hold up an async worker.
+async fn generate_banner(
+    state: &Arc<AppState>,
+    version: GeneratorVersion,
+    seed: Option<Seed>,
+    query: &GetBannerQuery,
+) -> Result<(Seed, BannerDescription), (StatusCode, String)> {
+    let constraints = query_constraints(query, state)?;
+    let style = query_style(query)?;
+    query_profile(query, state)?;
+    let options = GenerateOptions {
+        base_color: map_base_color(query.base_color),
+        layers: map_layers(query.layers.clone()),
+        max_layers: query.max_layers,
+    };
+
+    let generate = move |state: &AppState, profile: Option<&str>| {
+        state
+            .generator(version)
+            .constrained(&constraints)
+            .styled(style)
+            .weighted(
+                profile
+                    .and_then(|p| state.profiles.get(p))
+                    .map(|p| &p.profile),
+            )
+            .generate(seed, options)
+            .map_err(generation_error)
+    };
+    match style {
+        Style::Random => generate(state, query.profile.as_deref()),
+        Style::Pretty => {
+            let (state, profile) = (state.clone(), query.profile.clone());
+            tokio::task::spawn_blocking(move || generate(&state, profile.as_deref()))
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        }
+    }
+}
 
-    Ok((headers, bytes))
+/// See [`parse_constraints`]
+fn query_constraints(
+    query: &GetBannerQuery,
+    state: &AppState,
+) -> Result<Constraints, (StatusCode, String)> {
+    parse_constraints(query, &state.registry).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+fn query_style(query: &GetBannerQuery) -> Result<Style, (StatusCode, String)> {
+    match &query.style {
+        Some(style) => style
+            .parse()
+            .map_err(|e: ParseStyleError| (StatusCode::BAD_REQUEST, e.to_string())),
+        None => Ok(Style::default()),
+    }
+}
+
+fn query_profile<'a>(
+    query: &GetBannerQuery,
+    state: &'a AppState,
+) -> Result<Option<&'a NamedProfile>, (StatusCode, String)> {
+    match &query.profile {
+        Some(name) => match state.profiles.get(name) {
+            Some(profile) => Ok(Some(profile)),
+            None => Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown profile '{name}', see /metadata for every profile"),
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+/// The version pinned by the seed or `v=`, otherwise the configured default
+fn resolve_version(
+    seed: Option<VersionedSeed>,
+    query_version: Option<&str>,
+    state: &AppState,
+) -> Result<GeneratorVersion, (StatusCode, String)> {
+    let query_version = match query_version {
+        Some(v) => Some(
+            v.parse::<GeneratorVersion>()
+                .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?,
+        ),
+        None => None,
+    };
+
+    match (seed.and_then(|s| s.version), query_version) {
+        (Some(seed), Some(query)) if seed != query => Err((
+            StatusCode::BAD_REQUEST,
+            format!("The seed is pinned to {seed} but 'v' asks for {query}"),
+        )),
+        (Some(version), _) | (None, Some(version)) => Ok(version),
+        (None, None) => Ok(state.config.generator_version),
+    }
+}
+
+/// Bad input is the client's fault, anything else is ours
+fn generation_error(e: GenerationError) -> (StatusCode, String) {
+    let status = match e {
+        GenerationError::SeedOutOfRange { .. }
+        | GenerationError::TooManyLayers
+        | GenerationError::MissingLayer { .. }
+        | GenerationError::UnknownPattern { .. }
+        | GenerationError::UnsupportedConstraints { .. }
+        | GenerationError::NoColors
+        | GenerationError::UnsupportedProfile { .. }
+        | GenerationError::InvalidWeights { .. } => StatusCode::BAD_REQUEST,
+        GenerationError::Overflow | GenerationError::Render(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+/// The permanent `/banner/{seed}` url for a seed, with the same query arguments
+fn seed_location(seed: VersionedSeed, raw_query: Option<String>) -> HeaderValue {
+    let location = match raw_query {
+        Some(query) => format!("/banner/{seed}?{query}"),
+        None => format!("/banner/{seed}"),
+    };
+
+    // the query string was already a valid part of the request uri
+    HeaderValue::from_str(&location).unwrap()
+}
+
+/// Makes sure `banners` next to each other at a requested `width` (height is `width * 2`)
+/// fit in the output pixel budget.
+///
+/// Done before any rendering so huge sizes never reach `imageops::resize`.
+fn check_output_size(
+    width: Option<u32>,
+    banners: u64,
+    max_pixels: u64,
+) -> Result<(), (StatusCode, String)> {
+    let width = match width {
+        Some(width) => width as u64,
+        None => return Ok(()),
+    };
+
+    if width == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "'width' must be above 0".to_string(),
+        ));
+    }
+    // huge widths overflow even a u64, those are way over any budget too
+    let pixels = width
+        .checked_mul(width * 2)
+        .and_then(|pixels| pixels.checked_mul(banners));
+    if pixels.is_none_or(|pixels| pixels > max_pixels) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("'width' is too big, the image can be at most {max_pixels} pixels"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// `(width, height)` of `banners` next to each other, each `width` wide.
+///
+/// [`check_output_size`] already keeps these small, this only makes sure they never wrap around.
+fn output_size(width: u32, banners: usize) -> Result<(u32, u32), (StatusCode, String)> {
+    let too_big = || {
+        (
+            StatusCode::BAD_REQUEST,
+            "'width' is too big for an image".to_string(),
+        )
+    };
+    let banners = u32::try_from(banners).map_err(|_| too_big())?;
+    Ok((
+        width.checked_mul(banners).ok_or_else(too_big)?,
+        width.checked_mul(2).ok_or_else(too_big)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, router};
+    use axum::{body::Body, extract::ConnectInfo, http::Request};
+    use minecraft_banners::test_support::shape_registry;
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    async fn get(state: &Arc<AppState>, uri: &str, etag: Option<&HeaderValue>) -> Response {
+        let mut request = Request::get(uri);
+        if let Some(etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 0))));
+        router(state.clone())
+            .unwrap()
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn not_modified_keeps_the_cache_control() {
+        let registry = shape_registry(&[|x, _| x < 10, |_, y| y < 20]);
+        let state = Arc::new(AppState::new(Config::from_env().unwrap(), registry).unwrap());
+
+        for (uri, cache_control) in [
+            ("/banner/123", CACHE_UNPINNED),
+            ("/banner/v1-123", CACHE_IMMUTABLE),
+        ] {
+            let response = get(&state, uri, None).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let etag = response.headers()[header::ETAG].clone();
+
+            let response = get(&state, uri, Some(&etag)).await;
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{uri}");
+            assert_eq!(response.headers()[header::CACHE_CONTROL], cache_control);
+        }
+        assert!(!CACHE_UNPINNED.contains("immutable"));
+        assert!(CACHE_UNPINNED.contains("max-age=86400"));
+    }
+
+    #[tokio::test]
+    async fn blazons_dont_mix_with_layers() {
+        let registry = shape_registry(&[|x, _| x < 10, |_, y| y < 20]);
+        let state = Arc::new(AppState::new(Config::from_env().unwrap(), registry).unwrap());
+
+        let response = get(&state, "/create?blazon=Azure", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        for uri in [
+            "/create?blazon=Azure&base_color=3",
+            "/create?blazon=Azure&layers=[1,2]",
+        ] {
+            let response = get(&state, uri, None).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
+
+    #[test]
+    fn output_size_never_overflows() {
+        let max = 2_097_152;
+        assert!(check_output_size(None, 1, max).is_ok());
+        assert!(check_output_size(Some(1024), 1, max).is_ok());
+        assert!(check_output_size(Some(1025), 1, max).is_err());
+        assert!(check_output_size(Some(0), 1, max).is_err());
+
+        let (status, _) = check_output_size(Some(u32::MAX), 1, max).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // rows of banners for /text, 2^31 * 2^31 * 2 * 2 is exactly 2^64 which wraps around to 0
+        for banners in [2, 32] {
+            assert!(check_output_size(Some(u32::MAX), banners, max).is_err());
+        }
+        assert!(check_output_size(Some(1 << 31), 2, u64::MAX).is_err());
+        assert!(output_size(1 << 31, 2).is_err());
+        assert_eq!(output_size(64, 3).unwrap(), (192, 128));
+    }
 }
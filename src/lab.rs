@@ -0,0 +1,77 @@
+//! CIELAB colors, so colors can be compared the way eyes see them instead of by raw RGB.
+//!
+//! Also has the WCAG luminance & contrast ratio, for how well one color stands out on another.
+
+use std::sync::LazyLock;
+
+/// sRGB channel value to linear light, for every possible `u8`
+static LINEAR: LazyLock<[f32; 256]> = LazyLock::new(|| {
+    let mut table = [0.0; 256];
+    for (value, linear) in table.iter_mut().enumerate() {
+        let c = value as f32 / 255.0;
+        *linear = if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        };
+    }
+    table
+});
+
+/// D65 reference white
+const WHITE: (f32, f32, f32) = (0.95047, 1.0, 1.08883);
+
+/// A color in CIELAB (D65), `l` goes from 0 to 100
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Lab {
+    pub fn from_rgb((red, green, blue): (u8, u8, u8)) -> Self {
+        let (r, g, b) = (
+            LINEAR[red as usize],
+            LINEAR[green as usize],
+            LINEAR[blue as usize],
+        );
+
+        let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / WHITE.0;
+        let y = (0.2126729 * r + 0.7151522 * g + 0.0721750 * b) / WHITE.1;
+        let z = (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / WHITE.2;
+
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    /// The CIE76 color difference, around `2.3` is the smallest difference people notice
+    pub fn delta_e(&self, other: &Lab) -> f32 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+}
+
+/// WCAG relative luminance, `0` for black up to `1` for white
+pub fn relative_luminance((red, green, blue): (u8, u8, u8)) -> f32 {
+    0.2126 * LINEAR[red as usize] + 0.7152 * LINEAR[green as usize] + 0.0722 * LINEAR[blue as usize]
+}
+
+/// WCAG contrast ratio, from `1` (the same) to `21` (black on white). The order doesn't matter
+pub fn contrast_ratio(a: (u8, u8, u8), b: (u8, u8, u8)) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn f(t: f32) -> f32 {
+    const DELTA: f32 = 6.0 / 29.0;
+    if t > DELTA * DELTA * DELTA {
+        t.cbrt()
+    } else {
+        t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+    }
+}
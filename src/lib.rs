@@ -0,0 +1,50 @@
+//! The banner generation core of the server, without any web framework attached.
+//!
+//! A [`PatternRegistry`] holds the loaded pattern textures & renders banners,
+//! a [`BannerGenerator`] turns seeds into [`BannerDescription`]s.
+//! The server, CLIs & tests all go through these, so a seed means the same banner everywhere.
+//!
+//! ```no_run
+//! use minecraft_banners::{BannerGenerator, GenerateOptions, PatternRegistry};
+//!
+//! let registry = PatternRegistry::load("patterns")?;
+//! let generator = BannerGenerator::new(&registry);
+//!
+//! let (seed, banner) = generator.generate(Some(42), GenerateOptions::default())?;
+//! registry.render(&banner)?.save(format!("{seed}.png"))?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use image::{ImageBuffer, Rgba};
+
+pub mod blazon;
+pub mod combinations;
+pub mod distinct;
+pub mod generation;
+pub mod lab;
+pub mod palette;
+pub mod registry;
+pub mod similarity;
+pub mod solver;
+pub mod style;
+#[cfg(any(test, feature = "test-support"))]
+#[doc(hidden)]
+pub mod test_support;
+pub mod text;
+
+pub use blazon::{BlazonError, blazon, parse_blazon};
+pub use combinations::Combinations;
+pub use distinct::distinct_combinations;
+pub use generation::{
+    BannerDescription, BannerGenerator, Constraints, GenerateOptions, GenerationError,
+    GeneratorVersion, LayerOverride, ParseVersionError, Profile, Seed, VersionedSeed, parse_seed,
+};
+pub use lab::Lab;
+pub use palette::{LayerContrast, Palette, analyze};
+pub use registry::PatternRegistry;
+pub use similarity::{Features, Neighbor, similar};
+pub use solver::{Solution, SolveOptions, Solver};
+pub use style::{ParseStyleError, Style, score};
+pub use text::TextError;
+
+pub type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
@@ -0,0 +1,71 @@
+use axum::Router;
+use std::{
+    net::SocketAddr,
+    sync::{Arc, atomic::Ordering},
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::Notify};
+
+use crate::AppState;
+
+/// How long in-flight requests get to finish after a shutdown signal
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the app until a shutdown signal is received.
+///
+/// Once the signal arrives the server stops accepting connections,
+/// `/readyz` starts failing and in-flight requests get [`DRAIN_TIMEOUT`] to finish
+/// before they're dropped.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    state: Arc<AppState>,
+) -> std::io::Result<()> {
+    let draining = Arc::new(Notify::new());
+
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server = axum::serve(listener, app).with_graceful_shutdown({
+        let draining = draining.clone();
+        async move {
+            shutdown_signal().await;
+            tracing::info!("Shutting down, draining requests");
+            state.ready.store(false, Ordering::Relaxed);
+            draining.notify_one();
+        }
+    });
+
+    tokio::select! {
+        res = server => res,
+        _ = async {
+            draining.notified().await;
+            tokio::time::sleep(DRAIN_TIMEOUT).await;
+        } => {
+            tracing::warn!("Requests didn't drain within {DRAIN_TIMEOUT:?}, shutting down anyway");
+            Ok(())
+        }
+    }
+}
+
+/// Resolves once a Ctrl-C or SIGTERM is received
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for Ctrl-C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => (),
+        _ = terminate => (),
+    }
+}
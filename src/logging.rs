@@ -0,0 +1,131 @@
+use anyhow::{Result, anyhow};
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::{fmt, str::FromStr, time::Instant};
+use tracing::{Instrument, field::Empty};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Config;
+
+/// Identifies a request in the logs, taken from the client if it sent a sane one
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client supplied request id that gets reused instead of replaced
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// How log lines are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable, for local development
+    Pretty,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+#[derive(Debug)]
+pub struct ParseLogFormatError;
+
+impl fmt::Display for ParseLogFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected 'pretty' or 'json'")
+    }
+}
+
+impl std::error::Error for ParseLogFormatError {}
+
+impl FromStr for LogFormat {
+    type Err = ParseLogFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(ParseLogFormatError),
+        }
+    }
+}
+
+/// Sets up the global tracing subscriber, call once at startup.
+///
+/// `RUST_LOG` takes priority over [`Config::log_level`] when it's set.
+pub fn init(config: &Config) -> Result<()> {
+    let filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => EnvFilter::try_new(&config.log_level)?,
+    };
+
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match config.log_format {
+        LogFormat::Pretty => subscriber.try_init(),
+        LogFormat::Json => subscriber
+            .json()
+            .flatten_event(true)
+            .with_span_list(false)
+            .try_init(),
+    }
+    .map_err(|e| anyhow!(e))
+}
+
+fn request_id(request: &Request) -> HeaderValue {
+    let incoming = request.headers().get(X_REQUEST_ID).filter(|id| {
+        let id = id.as_bytes();
+        !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.iter().all(|b| b.is_ascii_graphic())
+    });
+
+    match incoming {
+        Some(id) => id.clone(),
+        None => HeaderValue::try_from(format!("{:016x}", rand::random::<u64>())).unwrap(),
+    }
+}
+
+/// Middleware wrapping every request in a span and logging it once it's done.
+///
+/// Handlers fill in the `seed`, `version`, `layers`, `render_ms` & `encode_ms` fields
+/// through [`tracing::Span::current`], the request id is echoed back as `X-Request-Id`.
+pub async fn trace_requests(request: Request, next: Next) -> Response {
+    let id = request_id(&request);
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_owned(),
+        None => "unmatched".to_owned(),
+    };
+
+    let span = tracing::info_span!(
+        "request",
+        id = id.to_str().unwrap_or_default(),
+        method = %request.method(),
+        route,
+        seed = Empty,
+        version = Empty,
+        layers = Empty,
+        render_ms = Empty,
+        encode_ms = Empty,
+        status = Empty,
+        latency_ms = Empty,
+    );
+
+    let start = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+
+    let status = response.status();
+    span.record("status", status.as_u16());
+    span.record("latency_ms", millis(start));
+    span.in_scope(|| {
+        if status.is_server_error() {
+            tracing::error!("request failed");
+        } else {
+            tracing::info!("request finished");
+        }
+    });
+
+    response.headers_mut().insert(X_REQUEST_ID, id);
+    response
+}
+
+/// Milliseconds since `start`, as a float so sub millisecond renders still show up
+pub fn millis(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
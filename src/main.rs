@@ -1,141 +1,179 @@
 use anyhow::Result;
-use axum::{Router, routing::get};
-use image::{ImageBuffer, ImageReader, Rgba};
-use mcb::*;
-use rand::seq::IndexedRandom;
-use rand_chacha::ChaCha8Rng;
+use axum::{
+    Router, middleware,
+    routing::{get, post},
+};
+use minecraft_banners::{BannerGenerator, Combinations, GeneratorVersion, PatternRegistry, Solver};
 use std::{
-    fs::{read_dir, read_to_string, write},
-    sync::Arc,
+    collections::HashMap,
+    sync::{Arc, OnceLock, atomic::AtomicBool},
 };
-use tokio::sync::Mutex;
 
-use crate::handlers::*;
+use crate::{
+    config::Config,
+    handlers::*,
+    logging::trace_requests,
+    metrics::{Metrics, get_metrics, track_metrics},
+    openapi::get_openapi,
+    ratelimit::{RateLimiter, rate_limit},
+    render_cache::RenderCache,
+    stats::{BannerCounter, spawn_flush_task},
+};
 
 mod bitbanner;
-mod generation;
+mod caching;
+mod config;
+mod cors;
 mod handlers;
+mod lifecycle;
+mod logging;
+mod metrics;
+mod openapi;
+mod precomputed;
+mod profiles;
 mod query;
+mod ratelimit;
+mod render_cache;
+mod responses;
+mod stats;
 
 use mimalloc::MiMalloc;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
-
 #[derive(Debug)]
 pub struct AppState {
-    patterns: Vec<(String, Image)>,
-    base: Image,
-    banner_count: Mutex<u64>,
+    registry: PatternRegistry,
+    banner_count: Arc<BannerCounter>,
+    /// If the server should receive traffic, false if patterns failed to load or when shutting down
+    ready: AtomicBool,
+    metrics: Metrics,
+    config: Config,
+    /// See [`caching::registry_version`]
+    registry_version: u64,
+    rate_limiter: RateLimiter,
+    render_cache: RenderCache,
+    /// Loaded from `distinct.json` if it matches the patterns, see [`precomputed::run_distinct`]
+    distinct: Option<Combinations>,
+    /// Loaded from [`Config::profiles_path`], by name
+    profiles: HashMap<String, profiles::NamedProfile>,
+    /// Every pattern tinted in every color for `/solve`, see [`AppState::solver`]
+    solver: OnceLock<Solver>,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let state = AppState {
-        patterns: load_patterns("patterns")?,
-        base: Banner::load_base()?,
-        banner_count: Mutex::new(load_banner_count()?),
-    };
+impl AppState {
+    fn new(config: Config, registry: PatternRegistry) -> Result<Self> {
+        let registry_version = caching::registry_version(&registry);
+        let distinct = precomputed::load_distinct(registry_version).unwrap_or_else(|e| {
+            tracing::error!("Failed to load the distinct banner count: {e:#}");
+            None
+        });
+        let profiles = profiles::load(&config.profiles_path, &registry)?;
+
+        Ok(AppState {
+            ready: AtomicBool::new(!registry.is_empty()),
+            registry_version,
+            registry,
+            banner_count: Arc::new(BannerCounter::load()?),
+            metrics: Metrics::new(),
+            rate_limiter: RateLimiter::new(config.json_limit, config.render_limit),
+            render_cache: RenderCache::new(config.cache_max_entries, config.cache_max_bytes),
+            config,
+            distinct,
+            profiles,
+            solver: OnceLock::new(),
+        })
+    }
+
+    fn generator(&self, version: GeneratorVersion) -> BannerGenerator<'_> {
+        BannerGenerator::with_version(&self.registry, version)
+    }
+
+    /// The [`Solver`] for the loaded patterns, built on the first `/solve` so startup stays quick.
+    ///
+    /// The first few requests at once might each build one, only the first to finish is kept.
+    fn solver(&self) -> Result<&Solver> {
+        if let Some(solver) = self.solver.get() {
+            return Ok(solver);
+        }
+        let solver = Solver::new(&self.registry)?;
+        Ok(self.solver.get_or_init(|| solver))
+    }
+}
 
-    let app = Router::new()
-        .route(
-            "/",
-            get(async || "Every place you've ever imagined, it's real"),
-        )
+/// Every route the server has, new ones also need to be added to [`openapi::ApiDoc`]
+fn router(state: Arc<AppState>) -> Result<Router> {
+    let cors = cors::layer(&state.config)?;
+
+    let mut router = Router::new()
+        .route("/", get(get_root))
         .route("/create", get(create_banner))
-        .route("/banner", get(get_banner))
+        .route("/banner", get(get_random_banner))
         .route("/banner/{seed}", get(get_banner))
-        .route("/pattern", get(get_pattern_list))
+        .route("/pattern", get(get_random_pattern_list))
         .route("/pattern/{seed}", get(get_pattern_list))
+        .route("/random", get(get_random))
         .route("/seed", get(get_new_seed))
+        .route("/solve", post(solve_banner))
+        .route("/compare", get(get_compare))
+        .route("/similar/{seed}", get(get_similar))
+        .route("/analyze/{seed}", get(get_analyze))
+        .route("/blazon/{seed}", get(get_blazon))
+        .route("/text", get(get_text))
+        .route("/text/recipes", get(get_text_recipes))
         .route("/metadata", get(get_metadata))
-        .with_state(Arc::new(state));
-
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8213").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
-
-    Ok(())
-}
-
-fn map_base_color(base_color: Option<u8>) -> Option<Color> {
-    base_color.map(|c| Color::from_repr(c)).flatten()
-}
+        .route("/healthz", get(get_health))
+        .route("/readyz", get(get_ready))
+        .route("/metrics", get(get_metrics))
+        .route("/openapi.json", get(get_openapi))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+        .route_layer(middleware::from_fn_with_state(state.clone(), track_metrics));
+
+    // preflights are answered here, before rate limiting & metrics
+    if let Some(cors) = cors {
+        router = router.layer(cors);
+    }
 
-fn random_color(rng: &mut ChaCha8Rng) -> &Color {
-    Color::all().choose(rng).unwrap()
+    Ok(router
+        .layer(middleware::from_fn(trace_requests))
+        .with_state(state))
 }
 
-fn load_patterns(dir: impl AsRef<std::path::Path>) -> Result<Vec<(String, Image)>> {
-    let pattern_files = read_dir(&dir.as_ref())?;
-    let mut patterns = Vec::new();
-
-    for file in pattern_files {
-        let file = file?;
-        let id = file
-            .file_name()
-            .to_string_lossy()
-            .split_once('.')
-            .unwrap()
-            .0
-            .to_string();
-
-        // this excludes the .gitkeep file
-        if id.is_empty() {
-            continue;
-        }
-
-        let img = ImageReader::open(file.path())?.decode()?.to_rgba8();
-
-        patterns.push((id, img));
+#[tokio::main]
+async fn main() -> Result<()> {
+    // the only subcommand, everything else starts the server
+    if std::env::args().nth(1).as_deref() == Some("distinct") {
+        return precomputed::run_distinct();
     }
 
-    // make sure theyre consistently in the same order
-    patterns.sort_by(|a, b| a.0.cmp(&b.0));
+    let config = Config::from_env()?;
+    logging::init(&config)?;
 
-    Ok(patterns)
-}
-
-const BANNER_STAT_FILE: &'static str = "count.txt";
-fn load_banner_count() -> Result<u64> {
-    let data = read_to_string(BANNER_STAT_FILE).unwrap_or("0".to_string());
-    Ok(data.parse::<u64>()?)
-}
-fn save_banner_count(num: u64) -> Result<()> {
-    write(BANNER_STAT_FILE, num.to_string())?;
-    Ok(())
-}
-async fn increment_banner_count(num: &Mutex<u64>) {
-    let mut num = num.lock().await;
-    *num += 1;
-    if *num % 100 == 0 {
-        // ignore error
-        match save_banner_count(*num) {
-            Err(e) => {
-                println!("{e:?}");
-            }
-            _ => (),
+    // still start up without patterns, so /healthz & /readyz can report it
+    let registry = match PatternRegistry::load("patterns") {
+        Ok(registry) => registry,
+        Err(e) => {
+            tracing::error!("Failed to load patterns: {e:#}");
+            PatternRegistry::new(Vec::new())?
         }
-    }
-}
-
-fn banner_from_pattern_list(
-    rng: &mut ChaCha8Rng,
-    base: &mut Image,
-    base_color: Option<Color>,
-    patterns: Vec<(usize, Color)>,
-    pattern_ref: &Vec<(String, Image)>,
-) -> Result<Image> {
-    let base_color = match base_color {
-        Some(color) => color,
-        None => *random_color(rng),
     };
-    let mut banner = Banner::new(base, base_color)?;
 
-    for (pattern_id, color) in patterns {
-        let pattern = Pattern::new(pattern_ref[pattern_id].1.clone());
-        banner.add_pattern(pattern, &color)?;
-    }
+    let state = Arc::new(AppState::new(config, registry)?);
+    let flush_task = spawn_flush_task(state.banner_count.clone());
+    let app = router(state.clone())?;
+
+    let listener = tokio::net::TcpListener::bind("0.0.0.0:8213").await.unwrap();
+    tracing::info!(
+        "Listening on {} with {} patterns",
+        listener.local_addr()?,
+        state.registry.len()
+    );
+    let served = lifecycle::serve(listener, app, state.clone()).await;
+
+    // final flush, even if the server itself errored
+    flush_task.abort();
+    state.banner_count.save()?;
+    served?;
 
-    Ok(banner.img_owned())
+    Ok(())
 }
@@ -0,0 +1,283 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{AppState, render_cache::RenderCache};
+
+/// Bucket upper bounds in seconds for request latency
+const REQUEST_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+];
+/// Bucket upper bounds in seconds for banner rendering, way faster than a whole request
+const RENDER_BUCKETS: &[f64] = &[
+    0.00001, 0.000025, 0.00005, 0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01,
+];
+
+/// A Prometheus style cumulative histogram
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Not cumulative, one per bound + the `+Inf` bucket
+    buckets: Vec<AtomicU64>,
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Histogram {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum_nanos: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|b| secs <= *b)
+            .unwrap_or(self.bounds.len());
+
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = match self.bounds.get(i) {
+                Some(b) => b.to_string(),
+                None => "+Inf".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{{labels}{sep}le=\"{le}\"}} {cumulative}"
+            );
+        }
+
+        let braces = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let sum = self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+        let _ = writeln!(out, "{name}_sum{braces} {sum}");
+        let _ = writeln!(
+            out,
+            "{name}_count{braces} {}",
+            self.count.load(Ordering::Relaxed)
+        );
+    }
+}
+
+#[derive(Debug)]
+struct RouteMetrics {
+    /// Request count per status code
+    statuses: RwLock<BTreeMap<u16, AtomicU64>>,
+    latency: Histogram,
+}
+
+/// Every metric exposed on `/metrics`
+#[derive(Debug)]
+pub struct Metrics {
+    routes: RwLock<BTreeMap<String, RouteMetrics>>,
+    errors: RwLock<BTreeMap<&'static str, AtomicU64>>,
+    /// Time spent picking patterns and compositing the banner
+    pub render_generation: Histogram,
+    /// Time spent resizing and encoding the banner to an image format
+    pub render_encoding: Histogram,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            routes: RwLock::new(BTreeMap::new()),
+            errors: RwLock::new(BTreeMap::new()),
+            render_generation: Histogram::new(RENDER_BUCKETS),
+            render_encoding: Histogram::new(RENDER_BUCKETS),
+        }
+    }
+
+    fn record_request(&self, route: &str, status: StatusCode, latency: Duration) {
+        if !self.routes.read().unwrap().contains_key(route) {
+            self.routes
+                .write()
+                .unwrap()
+                .entry(route.to_string())
+                .or_insert_with(|| RouteMetrics {
+                    statuses: RwLock::new(BTreeMap::new()),
+                    latency: Histogram::new(REQUEST_BUCKETS),
+                });
+        }
+
+        let routes = self.routes.read().unwrap();
+        let metrics = &routes[route];
+        metrics.latency.observe(latency);
+        increment(&metrics.statuses, status.as_u16());
+    }
+
+    /// Counts an error, `kind` is a short description like `Bad Request`
+    pub fn record_error(&self, kind: &'static str) {
+        increment(&self.errors, kind);
+    }
+
+    /// Renders everything in the Prometheus text exposition format
+    pub fn render(&self, banner_count: u64, cache: &RenderCache) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP http_requests_total Total HTTP requests by route and status.\n");
+        out.push_str("# TYPE http_requests_total counter\n");
+        let routes = self.routes.read().unwrap();
+        for (route, metrics) in routes.iter() {
+            for (status, count) in metrics.statuses.read().unwrap().iter() {
+                let _ = writeln!(
+                    out,
+                    "http_requests_total{{route=\"{route}\",status=\"{status}\"}} {}",
+                    count.load(Ordering::Relaxed)
+                );
+            }
+        }
+
+        out.push_str("# HELP http_request_duration_seconds HTTP request latency by route.\n");
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for (route, metrics) in routes.iter() {
+            metrics.latency.render(
+                &mut out,
+                "http_request_duration_seconds",
+                &format!("route=\"{route}\""),
+            );
+        }
+        drop(routes);
+
+        out.push_str("# HELP banner_render_duration_seconds Banner render time by stage.\n");
+        out.push_str("# TYPE banner_render_duration_seconds histogram\n");
+        self.render_generation.render(
+            &mut out,
+            "banner_render_duration_seconds",
+            "stage=\"generation\"",
+        );
+        self.render_encoding.render(
+            &mut out,
+            "banner_render_duration_seconds",
+            "stage=\"encoding\"",
+        );
+
+        out.push_str("# HELP errors_total Errors by kind.\n");
+        out.push_str("# TYPE errors_total counter\n");
+        for (kind, count) in self.errors.read().unwrap().iter() {
+            let kind = kind.to_lowercase().replace([' ', '-'], "_");
+            let _ = writeln!(
+                out,
+                "errors_total{{kind=\"{kind}\"}} {}",
+                count.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str("# HELP render_cache_requests_total Render cache lookups by result.\n");
+        out.push_str("# TYPE render_cache_requests_total counter\n");
+        let _ = writeln!(
+            out,
+            "render_cache_requests_total{{result=\"hit\"}} {}",
+            cache.hits.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            out,
+            "render_cache_requests_total{{result=\"miss\"}} {}",
+            cache.misses.load(Ordering::Relaxed)
+        );
+        out.push_str(
+            "# HELP render_cache_evictions_total Entries evicted from the render cache.\n",
+        );
+        out.push_str("# TYPE render_cache_evictions_total counter\n");
+        let _ = writeln!(
+            out,
+            "render_cache_evictions_total {}",
+            cache.evictions.load(Ordering::Relaxed)
+        );
+        let (entries, bytes) = cache.usage();
+        out.push_str("# HELP render_cache_entries Encoded banners currently cached.\n");
+        out.push_str("# TYPE render_cache_entries gauge\n");
+        let _ = writeln!(out, "render_cache_entries {entries}");
+        out.push_str("# HELP render_cache_bytes Approximate bytes used by the render cache.\n");
+        out.push_str("# TYPE render_cache_bytes gauge\n");
+        let _ = writeln!(out, "render_cache_bytes {bytes}");
+
+        out.push_str("# HELP banners_generated_total Total banners ever generated.\n");
+        out.push_str("# TYPE banners_generated_total counter\n");
+        let _ = writeln!(out, "banners_generated_total {banner_count}");
+
+        out
+    }
+}
+
+/// Increments a lazily inserted counter, only write locks the first time a key is seen
+fn increment<K: Ord + Copy>(map: &RwLock<BTreeMap<K, AtomicU64>>, key: K) {
+    if let Some(count) = map.read().unwrap().get(&key) {
+        count.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    map.write()
+        .unwrap()
+        .entry(key)
+        .or_insert_with(|| AtomicU64::new(0))
+        .fetch_add(1, Ordering::Relaxed);
+}
+
+/// Middleware recording request counts, latency and errors for every matched route
+pub async fn track_metrics(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_owned(),
+        None => "unmatched".to_owned(),
+    };
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let latency = start.elapsed();
+
+    let status = response.status();
+    state.metrics.record_request(&route, status, latency);
+    if status.is_client_error() || status.is_server_error() {
+        let kind = status.canonical_reason().unwrap_or("Unknown");
+        state.metrics.record_error(kind);
+    }
+
+    response
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "server",
+    description = "Prometheus metrics.",
+    responses((status = 200, content_type = "text/plain", body = String)),
+)]
+pub async fn get_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let body = state
+        .metrics
+        .render(state.banner_count.get(), &state.render_cache);
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
@@ -0,0 +1,211 @@
+use axum::Json;
+use utoipa::{
+    OpenApi,
+    openapi::{InfoBuilder, OpenApi as OpenApiSpec},
+};
+
+use crate::{handlers, metrics, responses};
+
+/// The OpenAPI document for every route in [`crate::router`].
+///
+/// A committed copy lives in `openapi.json`, the tests below fail when either drifts from the handlers.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handlers::get_root,
+        handlers::create_banner,
+        handlers::get_random_banner,
+        handlers::get_banner,
+        handlers::get_random_pattern_list,
+        handlers::get_pattern_list,
+        handlers::get_random,
+        handlers::get_new_seed,
+        handlers::get_compare,
+        handlers::get_similar,
+        handlers::get_analyze,
+        handlers::get_blazon,
+        handlers::solve_banner,
+        handlers::get_text,
+        handlers::get_text_recipes,
+        handlers::get_metadata,
+        handlers::get_health,
+        handlers::get_ready,
+        metrics::get_metrics,
+        get_openapi,
+    ),
+    components(schemas(
+        responses::PatternListResponse,
+        responses::MetadataResponse,
+        responses::CompareResponse,
+        responses::CanonicalResponse,
+        responses::SimilarResponse,
+        responses::SimilarBanner,
+        responses::AnalyzeResponse,
+        responses::ColorCoverage,
+        responses::LabColor,
+        responses::LayerAnalysis,
+        responses::BlazonResponse,
+        responses::SolveResponse,
+        responses::TextResponse,
+        responses::LetterResponse,
+        responses::ReadyResponse,
+    )),
+    tags(
+        (name = "banners", description = "Rendered banner images"),
+        (name = "patterns", description = "Banner data as JSON"),
+        (name = "server", description = "Health checks, metrics etc"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The spec with the crate info filled in, what `/openapi.json` serves
+pub fn spec() -> OpenApiSpec {
+    let mut spec = ApiDoc::openapi();
+    spec.info = InfoBuilder::new()
+        .title("Minecraft Banners")
+        .version(env!("CARGO_PKG_VERSION"))
+        .description(Some("Generate random & custom minecraft banners"))
+        .build();
+    spec
+}
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "server",
+    description = "This document.",
+    responses(
+        (status = 200, description = "The OpenAPI 3 document", content_type = "application/json", body = Object),
+        (status = 429, description = "Rate limited", content_type = "text/plain", body = String),
+    )
+)]
+pub async fn get_openapi() -> Json<OpenApiSpec> {
+    Json(spec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AppState, config::Config, router};
+    use axum::{
+        body::{Body, to_bytes},
+        extract::ConnectInfo,
+        http::{Request, header},
+    };
+    use minecraft_banners::test_support::shape_registry;
+    use serde_json::Value;
+    use std::{net::SocketAddr, sync::Arc};
+    use tower::ServiceExt;
+
+    const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    /// Regenerate with `UPDATE_OPENAPI=1 cargo test`
+    #[test]
+    fn snapshot_is_up_to_date() {
+        let generated = spec().to_pretty_json().unwrap() + "\n";
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(SNAPSHOT, &generated).unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "openapi.json is out of date, rerun the tests with UPDATE_OPENAPI=1"
+        );
+    }
+
+    fn test_state() -> Arc<AppState> {
+        // full size textures, anything smaller gets cropped & fails to render
+        let registry = shape_registry(&[|_, y| y < 10, |_, y| y < 20, |_, y| y < 30, |_, _| true]);
+        Arc::new(AppState::new(Config::from_env().unwrap(), registry).unwrap())
+    }
+
+    /// Follows a `$ref` into `components`
+    fn resolve<'a>(spec: &'a Value, schema: &'a Value) -> &'a Value {
+        match schema["$ref"].as_str() {
+            Some(path) => spec
+                .pointer(path.trim_start_matches('#'))
+                .unwrap_or_else(|| panic!("dangling $ref {path}")),
+            None => schema,
+        }
+    }
+
+    /// Checks that a response body has exactly the properties its schema documents
+    fn check_body(spec: &Value, schema: &Value, body: &Value, route: &str) {
+        let schema = resolve(spec, schema);
+        let Some(properties) = schema["properties"].as_object() else {
+            return;
+        };
+        let body = body
+            .as_object()
+            .unwrap_or_else(|| panic!("{route}: expected an object"));
+
+        for key in body.keys() {
+            assert!(
+                properties.contains_key(key),
+                "{route}: undocumented field '{key}'"
+            );
+        }
+        for required in schema["required"].as_array().into_iter().flatten() {
+            let required = required.as_str().unwrap();
+            assert!(
+                body.contains_key(required),
+                "{route}: missing field '{required}'"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn every_path_matches_the_router() {
+        let state = test_state();
+        let spec = serde_json::to_value(spec()).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+
+        for (path, operations) in paths {
+            let uri = path.replace("{seed}", "42");
+            // every path only has one method
+            let (method, operation) = operations.as_object().unwrap().iter().next().unwrap();
+            let mut request = Request::builder()
+                .method(method.to_uppercase().as_str())
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 0))));
+
+            let response = router(state.clone())
+                .unwrap()
+                .oneshot(request)
+                .await
+                .unwrap();
+            let status = response.status();
+            assert!(!status.is_server_error(), "{path}: failed with {status}");
+            let documented = &operation["responses"][status.as_str()];
+            assert!(
+                !documented.is_null(),
+                "{path}: returned undocumented status {status}"
+            );
+
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .map(|h| h.to_str().unwrap().to_string());
+            let Some(content) = documented["content"].as_object() else {
+                continue;
+            };
+            let content_type = content_type.unwrap_or_else(|| panic!("{path}: no content type"));
+            let (documented_type, media) = content
+                .iter()
+                .find(|(t, _)| content_type.starts_with(t.as_str()))
+                .unwrap_or_else(|| panic!("{path}: undocumented content type {content_type}"));
+
+            if documented_type == "application/json" {
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: Value = serde_json::from_slice(&body).unwrap();
+                check_body(&spec, &media["schema"], &body, path);
+            }
+        }
+    }
+}
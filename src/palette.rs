@@ -0,0 +1,165 @@
+//! Which colors a banner is made of & how much each one shows.
+//!
+//! Layers blend by their alpha, so every pixel is split between the dyes of the base & the
+//! layers over it. That split is tracked bottom to top, which also tells what's beneath each layer.
+
+use mcb::{Banner, Color, Pattern, composite::PIXELS};
+
+use crate::{BannerDescription, Lab, PatternRegistry, lab::contrast_ratio};
+
+/// How a banner's colors add up, see [`analyze`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// Share of the banner every dye shows on, indexed by the color id, sums up to 1
+    pub coverage: [f32; 16],
+    /// Average of every rendered pixel, shading of the textures included
+    pub average: Lab,
+    /// Every layer against whatever it's on top of, bottom to top
+    pub layers: Vec<LayerContrast>,
+}
+
+impl Palette {
+    /// Every dye that shows, the one covering the most first
+    pub fn dominant(&self) -> Vec<(Color, f32)> {
+        let mut dominant = Color::all()
+            .iter()
+            .map(|color| (*color, self.coverage[*color as usize]))
+            .filter(|(_, coverage)| *coverage > 0.0)
+            .collect::<Vec<_>>();
+        dominant.sort_by(|a, b| b.1.total_cmp(&a.1));
+        dominant
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerContrast {
+    pub color: Color,
+    /// The dye that shows the most beneath the layer's mask
+    pub below: Color,
+    /// WCAG contrast ratio between `color` & `below`, `1` means the layer barely stands out
+    pub contrast: f32,
+}
+
+/// The palette of a banner, coverage & contrast go by the dye colors & pattern alpha masks
+pub fn analyze(registry: &PatternRegistry, banner: &BannerDescription) -> anyhow::Result<Palette> {
+    let render = registry.render(banner)?;
+
+    // how much of every pixel is each dye
+    let mut shares = vec![[0.0f32; 16]; PIXELS];
+    for share in &mut shares {
+        share[banner.base as usize] = 1.0;
+    }
+
+    let mut layers = Vec::with_capacity(banner.layers.len());
+    for (pattern_id, color) in &banner.layers {
+        let pattern = Pattern::new(registry.patterns()[*pattern_id].1.clone());
+        if pattern.dimensions() != (Banner::X, Banner::Y) {
+            return Err(mcb::Error::InvalidBannerDimension.into());
+        }
+
+        let mut beneath = [0.0f32; 16];
+        for (share, pixel) in shares.iter_mut().zip(pattern.pixels()) {
+            let alpha = pixel[3] as f32 / 255.0;
+            for (beneath, share) in beneath.iter_mut().zip(share.iter_mut()) {
+                *beneath += alpha * *share;
+                *share *= 1.0 - alpha;
+            }
+            share[*color as usize] += alpha;
+        }
+
+        // an empty mask isn't on top of anything but the base
+        let below = Color::all()
+            .iter()
+            .copied()
+            .max_by(|a, b| beneath[*a as usize].total_cmp(&beneath[*b as usize]))
+            .filter(|below| beneath[*below as usize] > 0.0)
+            .unwrap_or(banner.base);
+        layers.push(LayerContrast {
+            color: *color,
+            below,
+            contrast: contrast_ratio(color.rgb(), below.rgb()),
+        });
+    }
+
+    let mut coverage = [0.0; 16];
+    for share in &shares {
+        for (coverage, share) in coverage.iter_mut().zip(share) {
+            *coverage += share;
+        }
+    }
+    coverage.iter_mut().for_each(|c| *c /= PIXELS as f32);
+
+    let (mut l, mut a, mut b) = (0.0, 0.0, 0.0);
+    for pixel in render.pixels() {
+        let lab = Lab::from_rgb((pixel[0], pixel[1], pixel[2]));
+        l += lab.l;
+        a += lab.a;
+        b += lab.b;
+    }
+    let pixels = (render.width() * render.height()) as f32;
+
+    Ok(Palette {
+        coverage,
+        average: Lab {
+            l: l / pixels,
+            a: a / pixels,
+            b: b / pixels,
+        },
+        layers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::shape_registry;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    fn registry() -> PatternRegistry {
+        shape_registry(&[|_, y| y < 20, |_, _| true])
+    }
+
+    #[test]
+    fn coverage_follows_the_masks() {
+        let registry = registry();
+        let banner = BannerDescription {
+            base: Color::White,
+            layers: vec![(0, Color::Black)],
+        };
+        let palette = analyze(&registry, &banner).unwrap();
+
+        assert!(close(palette.coverage[Color::White as usize], 0.5));
+        assert!(close(palette.coverage[Color::Black as usize], 0.5));
+        assert_eq!(palette.layers[0].below, Color::White);
+        assert!(palette.layers[0].contrast > 15.0);
+    }
+
+    #[test]
+    fn covered_colors_dont_count() {
+        let registry = registry();
+        let banner = BannerDescription {
+            base: Color::White,
+            layers: vec![(1, Color::Red), (1, Color::Blue), (0, Color::Blue)],
+        };
+        let palette = analyze(&registry, &banner).unwrap();
+
+        let dominant = palette.dominant();
+        assert_eq!(dominant.len(), 1);
+        assert_eq!(dominant[0].0, Color::Blue);
+        assert!(close(dominant[0].1, 1.0));
+        assert_eq!(palette.layers[1].below, Color::Red);
+        // the same color on top of itself doesn't stand out at all
+        assert_eq!(palette.layers[2].below, Color::Blue);
+        assert!(close(palette.layers[2].contrast, 1.0));
+    }
+
+    #[test]
+    fn contrast_ratio_goes_from_1_to_21() {
+        assert!(close(contrast_ratio((0, 0, 0), (255, 255, 255)), 21.0));
+        assert!(close(contrast_ratio((255, 255, 255), (0, 0, 0)), 21.0));
+        assert!(close(contrast_ratio((80, 90, 100), (80, 90, 100)), 1.0));
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{Context, Result, bail};
+use mcb::Banner;
+use minecraft_banners::{Combinations, PatternRegistry, distinct_combinations};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, read_to_string, rename},
+    io::{ErrorKind, Write},
+    time::Instant,
+};
+
+use crate::caching;
+
+const DISTINCT_FILE: &str = "distinct.json";
+const DISTINCT_TMP_FILE: &str = "distinct.json.tmp";
+
+/// What `distinct` writes to disk, numbers are strings since they don't fit in JSON
+#[derive(Debug, Serialize, Deserialize)]
+struct DistinctFile {
+    /// [`caching::registry_version`] as hex, the count is only valid for the exact same patterns
+    registry_version: String,
+    total: String,
+    per_layer: Vec<String>,
+}
+
+/// Counts the visually distinct banners (see [`distinct_combinations`]) & writes them to `distinct.json`.
+///
+/// Too slow to do on every startup, run `cargo r -r -- distinct` whenever the patterns change.
+pub fn run_distinct() -> Result<()> {
+    let registry = PatternRegistry::load("patterns")?;
+    let start = Instant::now();
+    let distinct = distinct_combinations(&registry, Banner::VANILLA_MAX_PATTERN_SIZE)?;
+
+    for (layers, count) in distinct.per_layer.iter().enumerate() {
+        println!("{layers} layers: {count}");
+    }
+    println!(
+        "{} distinct banners at most, took {:?}",
+        distinct.total,
+        start.elapsed()
+    );
+
+    let file = DistinctFile {
+        registry_version: format!("{:016x}", caching::registry_version(&registry)),
+        total: distinct.total.to_string(),
+        per_layer: distinct.per_layer.iter().map(|c| c.to_string()).collect(),
+    };
+
+    // same as the banner count, never leave a half written file
+    let mut tmp = File::create(DISTINCT_TMP_FILE)?;
+    tmp.write_all(serde_json::to_string_pretty(&file)?.as_bytes())?;
+    tmp.sync_all()?;
+    rename(DISTINCT_TMP_FILE, DISTINCT_FILE)?;
+    println!("Saved to {DISTINCT_FILE}");
+
+    Ok(())
+}
+
+/// The precomputed distinct banner count, `None` if it hasn't been computed for these patterns
+pub fn load_distinct(registry_version: u64) -> Result<Option<Combinations>> {
+    let data = match read_to_string(DISTINCT_FILE) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let file: DistinctFile = serde_json::from_str(&data).context("Invalid distinct.json")?;
+
+    if file.registry_version != format!("{registry_version:016x}") {
+        tracing::warn!("{DISTINCT_FILE} is for different patterns, run `distinct` again");
+        return Ok(None);
+    }
+
+    let per_layer = file
+        .per_layer
+        .iter()
+        .map(|c| c.parse())
+        .collect::<Result<Vec<u128>, _>>()?;
+    let total = file.total.parse()?;
+    if per_layer
+        .iter()
+        .try_fold(0u128, |sum, c| sum.checked_add(*c))
+        != Some(total)
+    {
+        bail!("{DISTINCT_FILE} doesn't add up");
+    }
+
+    Ok(Some(Combinations { per_layer, total }))
+}
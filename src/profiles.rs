@@ -0,0 +1,103 @@
+use anyhow::{Context, Result, bail};
+use minecraft_banners::{PatternRegistry, Profile};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::read_to_string,
+    hash::Hasher,
+    io::ErrorKind,
+};
+
+use crate::{caching::Fnv64, query::parse_color};
+
+/// A profile as written in the profiles file, patterns & colors by name
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileEntry {
+    /// Pattern name (or id) to weight
+    #[serde(default)]
+    patterns: BTreeMap<String, f64>,
+    /// Dye name (or index) to weight
+    #[serde(default)]
+    colors: BTreeMap<String, f64>,
+    #[serde(default)]
+    layers: Vec<f64>,
+}
+
+/// A profile from the config, selected with `?profile=`
+#[derive(Debug)]
+pub struct NamedProfile {
+    pub profile: Profile,
+    /// Changes whenever the profile's weights do, so ETags do too
+    pub fingerprint: u64,
+}
+
+/// Loads every profile in `path` (a JSON object of profile names to weights), none if it doesn't exist
+pub fn load(path: &str, registry: &PatternRegistry) -> Result<HashMap<String, NamedProfile>> {
+    let data = match read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {path}")),
+    };
+    let entries: BTreeMap<String, serde_json::Value> =
+        serde_json::from_str(&data).with_context(|| format!("Invalid {path}"))?;
+
+    let mut profiles = HashMap::new();
+    for (name, value) in entries {
+        let mut hasher = Fnv64::new();
+        hasher.write(value.to_string().as_bytes());
+
+        let entry: ProfileEntry = serde_json::from_value(value)
+            .with_context(|| format!("Invalid profile '{name}' in {path}"))?;
+        let profile = profile(registry, entry)
+            .with_context(|| format!("Invalid profile '{name}' in {path}"))?;
+        profiles.insert(
+            name,
+            NamedProfile {
+                profile,
+                fingerprint: hasher.finish(),
+            },
+        );
+    }
+
+    Ok(profiles)
+}
+
+fn profile(registry: &PatternRegistry, entry: ProfileEntry) -> Result<Profile> {
+    let weights = entry
+        .patterns
+        .values()
+        .chain(entry.colors.values())
+        .chain(&entry.layers);
+    if let Some(weight) = weights.into_iter().find(|w| !w.is_finite() || **w < 0.0) {
+        bail!("Weights can't be negative, got {weight}");
+    }
+
+    let patterns = entry
+        .patterns
+        .into_iter()
+        .map(|(pattern, weight)| {
+            let id = match pattern.parse::<usize>() {
+                Ok(id) if id < registry.len() => id,
+                _ => registry
+                    .id(&pattern)
+                    .with_context(|| format!("Unknown pattern '{pattern}'"))?,
+            };
+            Ok((id, weight))
+        })
+        .collect::<Result<_>>()?;
+    let colors = entry
+        .colors
+        .into_iter()
+        .map(|(color, weight)| {
+            let color = parse_color(&color).with_context(|| format!("Unknown color '{color}'"))?;
+            Ok((color, weight))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Profile {
+        patterns,
+        colors,
+        layers: entry.layers,
+    })
+}
@@ -1,5 +1,7 @@
 use mcb::Color;
+use minecraft_banners::{BannerDescription, Constraints, LayerOverride, PatternRegistry};
 use serde::Deserialize;
+use utoipa::IntoParams;
 
 #[derive(Debug, Deserialize)]
 pub struct LayerEntry {
@@ -7,13 +9,77 @@ pub struct LayerEntry {
     pub color: Option<u8>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Hash, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GetBannerQuery {
+    /// Base color to use instead of a random seeded one, the color index (0-15).
     pub base_color: Option<u8>,
+    /// Layers overriding the random seeded ones, `[pattern, color]` with either being optional.
+    /// Example: `?layers=&layers=&layers=[2, 7]` sets layer 3.
     #[serde(default)]
     pub layers: Vec<Option<String>>,
+    /// How many layers to use.
     pub max_layers: Option<usize>,
+    /// Image width in pixels, height becomes `width * 2`. Only for image endpoints.
     pub width: Option<u32>,
+    /// Generator version (`v1`, `v2`) for seeds that aren't pinned to one, defaults to the server's default.
+    pub v: Option<String>,
+    /// Comma separated colors (dye names or indexes) random layers & the base can use, like `red,white,13`.
+    /// Seeds are only the same banner with the same constraints.
+    pub colors: Option<String>,
+    /// Comma separated patterns (ids or names) random layers can use.
+    pub patterns: Option<String>,
+    /// Comma separated patterns (ids or names) random layers never use.
+    pub exclude_patterns: Option<String>,
+    /// `random` (default) or `pretty`, which redraws layers that blend into what's beneath them
+    /// & drops hidden ones. Seeds are only the same banner with the same style.
+    pub style: Option<String>,
+    /// A weighted generation profile from `/metadata` to pick layers, patterns & colors by, needs `v2`
+    /// or later. Seeds are only the same banner with the same profile.
+    pub profile: Option<String>,
+    /// The whole banner as a heraldic blazon, like `Lime, a creeper charge sable, a bordure gules`.
+    /// Replaces `base_color` & `layers` (sending either too is a `400`), only for `/create`.
+    pub blazon: Option<String>,
+}
+
+#[derive(Debug, Hash, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TextQuery {
+    /// The text, letters, digits, spaces & `. - _ + / \ |`. Case doesn't matter.
+    pub value: String,
+    /// Letter color, a dye name like `light_blue` or the color index (0-15). Defaults to `black`.
+    pub fg: Option<String>,
+    /// Background color, same as `fg`. Defaults to `white`.
+    pub bg: Option<String>,
+    /// Width of every banner in pixels, height becomes `width * 2`. Only for `/text`.
+    pub width: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompareQuery {
+    /// A seed (`v2-123` or `123`) or a base color with layers like `/create`, written `0[12,15][4,3]`.
+    pub a: String,
+    /// Same as `a`.
+    pub b: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SolveQuery {
+    /// At most this many layers, defaults to 6.
+    pub max_layers: Option<usize>,
+    /// How many partial banners to keep while searching, higher is slower but can find closer ones. Defaults to 4.
+    pub beam_width: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SimilarQuery {
+    /// How many banners to return, defaults to 10.
+    pub count: Option<usize>,
+    /// Generator version (`v1`, `v2`) for seeds that aren't pinned to one, defaults to the server's default.
+    pub v: Option<String>,
 }
 
 // custom layer/pattern query format
@@ -46,46 +112,120 @@ pub fn parse_layer_entry(s: &str) -> LayerEntry {
     let id = if id.is_empty() {
         None
     } else {
-        match id.parse::<usize>() {
-            Ok(c) => Some(c),
-            Err(_) => None,
-        }
+        id.parse::<usize>().ok()
     };
 
     let color = if color.is_empty() {
         None
     } else {
-        match color.parse::<u8>() {
-            Ok(c) => Some(c),
-            Err(_) => None,
-        }
+        color.parse::<u8>().ok()
     };
 
     LayerEntry { id, color }
 }
 
-pub fn map_layers(layers: Vec<Option<String>>) -> Vec<Option<(Option<usize>, Option<Color>)>> {
+/// A dye name like `light_blue` or a color index (0-15)
+pub fn parse_color(color: &str) -> Option<Color> {
+    let color = color.trim();
+    match color.parse::<u8>() {
+        Ok(id) => Color::from_repr(id),
+        Err(_) => Color::from_name(color),
+    }
+}
+
+/// A banner written out as its base color followed by its layers, like `0[12,15][4,3]`.
+///
+/// Every layer needs both a pattern & a color, `5[]` is just the base.
+pub fn parse_banner(banner: &str) -> Option<BannerDescription> {
+    let banner = banner.trim();
+    let (base, layers) = banner.split_at(banner.find('[')?);
+    let base = Color::from_repr(base.trim().parse().ok()?)?;
+
+    let mut parsed = Vec::new();
+    for layer in layers.split_inclusive(']') {
+        let layer = layer.trim();
+        if !layer.starts_with('[') || !layer.ends_with(']') {
+            return None;
+        }
+        if layer[1..layer.len() - 1].trim().is_empty() {
+            continue;
+        }
+
+        match parse_layer_entry(layer) {
+            LayerEntry {
+                id: Some(id),
+                color: Some(color),
+            } => parsed.push((id, Color::from_repr(color)?)),
+            _ => return None,
+        }
+    }
+
+    Some(BannerDescription {
+        base,
+        layers: parsed,
+    })
+}
+
+pub fn map_base_color(base_color: Option<u8>) -> Option<Color> {
+    base_color.and_then(Color::from_repr)
+}
+
+pub fn map_layers(layers: Vec<Option<String>>) -> Vec<LayerOverride> {
     layers
         .into_iter()
-        .map(|l| {
-            let unit = match l {
-                Some(l) => {
-                    let entry = parse_layer_entry(&l);
-
-                    let color = match entry.color {
-                        Some(c) => match Color::from_repr(c) {
-                            Some(c) => Some(c),
-                            None => None,
-                        },
-                        None => None,
-                    };
-
-                    Some((entry.id, color))
-                }
-                None => None,
-            };
+        .map(|l| match l {
+            Some(l) => {
+                let entry = parse_layer_entry(&l);
 
-            unit
+                let color = match entry.color {
+                    Some(c) => Color::from_repr(c),
+                    None => None,
+                };
+
+                Some((entry.id, color))
+            }
+            None => None,
         })
-        .collect::<Vec<Option<(Option<usize>, Option<Color>)>>>()
+        .collect::<Vec<LayerOverride>>()
+}
+
+/// `a, b,c` to `a`, `b` & `c`
+fn list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// The `colors`, `patterns` & `exclude_patterns` of a query
+pub fn parse_constraints(
+    query: &GetBannerQuery,
+    registry: &PatternRegistry,
+) -> Result<Constraints, String> {
+    let pattern = |pattern: &str| match pattern.parse::<usize>() {
+        Ok(id) => Ok(id),
+        Err(_) => registry
+            .id(pattern)
+            .ok_or_else(|| format!("Unknown pattern '{pattern}'")),
+    };
+
+    let colors = match &query.colors {
+        Some(colors) => Some(
+            list(colors)
+                .map(|color| parse_color(color).ok_or_else(|| format!("Unknown color '{color}'")))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        None => None,
+    };
+    let patterns = match &query.patterns {
+        Some(patterns) => Some(list(patterns).map(pattern).collect::<Result<Vec<_>, _>>()?),
+        None => None,
+    };
+    let exclude_patterns = match &query.exclude_patterns {
+        Some(patterns) => list(patterns).map(pattern).collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(Constraints {
+        colors,
+        patterns,
+        exclude_patterns,
+    })
 }
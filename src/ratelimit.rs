@@ -0,0 +1,211 @@
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Query, Request, State},
+    http::{HeaderMap, StatusCode, Uri, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use minecraft_banners::Style;
+
+use crate::{AppState, config::RateLimit};
+
+/// How many buckets can pile up before the full (idle) ones get cleared out
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// What kind of work a route does, each class has its own limit
+#[derive(Debug, Clone, Copy)]
+pub enum RouteClass {
+    /// Cheap JSON/text responses
+    Json,
+    /// Renders and encodes an image, or other heavy work like solving one
+    Render,
+}
+
+impl RouteClass {
+    /// `None` for routes that are never limited (health checks, metrics etc)
+    fn from_route(route: &str) -> Option<Self> {
+        match route {
+            "/banner" | "/banner/{seed}" | "/create" | "/solve" | "/similar/{seed}" | "/text"
+            | "/compare" | "/analyze/{seed}" => Some(RouteClass::Render),
+            "/pattern" | "/pattern/{seed}" | "/random" | "/seed" | "/metadata"
+            | "/text/recipes" | "/blazon/{seed}" | "/openapi.json" => Some(RouteClass::Json),
+            _ => None,
+        }
+    }
+
+    /// [`RouteClass::from_route`], except `style=pretty` costs as much as a render everywhere
+    fn from_request(route: &str, uri: &Uri) -> Option<Self> {
+        let class = Self::from_route(route)?;
+        // /random only redirects, pretty banners are made on the url it points to
+        let generates = matches!(route, "/pattern" | "/pattern/{seed}" | "/blazon/{seed}");
+        if generates && is_pretty(uri) {
+            return Some(RouteClass::Render);
+        }
+        Some(class)
+    }
+}
+
+/// If the query asks for `style=pretty`, which redraws & renders layers until they look good
+fn is_pretty(uri: &Uri) -> bool {
+    let Ok(Query(query)) = Query::<Vec<(String, String)>>::try_from_uri(uri) else {
+        return false;
+    };
+    query
+        .iter()
+        .any(|(key, value)| key == "style" && matches!(value.parse(), Ok(Style::Pretty)))
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Per ip token buckets for a single [`RateLimit`]
+#[derive(Debug)]
+pub struct TokenBuckets {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl TokenBuckets {
+    pub fn new(limit: RateLimit) -> Self {
+        TokenBuckets {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token for `ip`, or returns how many seconds until one is available
+    fn take(&self, ip: IpAddr) -> Result<(), f64> {
+        if self.limit.per_second <= 0.0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= SWEEP_THRESHOLD {
+            let limit = self.limit;
+            buckets.retain(|_, b| {
+                b.tokens + now.duration_since(b.updated).as_secs_f64() * limit.per_second
+                    < limit.burst
+            });
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: self.limit.burst,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.limit.per_second).min(self.limit.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err((1.0 - bucket.tokens) / self.limit.per_second)
+        }
+    }
+}
+
+/// Every rate limit the server uses
+#[derive(Debug)]
+pub struct RateLimiter {
+    json: TokenBuckets,
+    render: TokenBuckets,
+}
+
+impl RateLimiter {
+    pub fn new(json: RateLimit, render: RateLimit) -> Self {
+        RateLimiter {
+            json: TokenBuckets::new(json),
+            render: TokenBuckets::new(render),
+        }
+    }
+
+    fn buckets(&self, class: RouteClass) -> &TokenBuckets {
+        match class {
+            RouteClass::Json => &self.json,
+            RouteClass::Render => &self.render,
+        }
+    }
+}
+
+fn client_ip(headers: &HeaderMap, addr: SocketAddr, trust_forwarded_for: bool) -> IpAddr {
+    if trust_forwarded_for {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.split(',').next())
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok());
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+
+    addr.ip()
+}
+
+/// Middleware that responds with `429 Too Many Requests` once an ip runs out of tokens
+pub async fn rate_limit(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let class = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| RouteClass::from_request(path.as_str(), request.uri()));
+    let Some(class) = class else {
+        return next.run(request).await;
+    };
+
+    let ip = client_ip(request.headers(), addr, state.config.trust_forwarded_for);
+    match state.rate_limiter.buckets(class).take(ip) {
+        Ok(_) => next.run(request).await,
+        Err(retry_after) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.ceil().max(1.0).to_string())],
+            "Too many requests, slow down",
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pretty_banners_count_as_renders() {
+        let class = |route, uri: &str| RouteClass::from_request(route, &uri.parse().unwrap());
+
+        assert!(matches!(
+            class("/pattern/{seed}", "/pattern/42"),
+            Some(RouteClass::Json)
+        ));
+        assert!(matches!(
+            class("/pattern/{seed}", "/pattern/42?layers=&style=%20Pretty"),
+            Some(RouteClass::Render)
+        ));
+        assert!(matches!(
+            class("/blazon/{seed}", "/blazon/42?style=pretty"),
+            Some(RouteClass::Render)
+        ));
+        assert!(matches!(
+            class("/random", "/random?style=pretty"),
+            Some(RouteClass::Json)
+        ));
+        assert!(class("/healthz", "/healthz?style=pretty").is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use image::{ImageReader, imageops};
+use mcb::{Banner, Color, Pattern, canonical::canonical_layers};
+use std::{fs::read_dir, path::Path};
+
+use crate::{BannerDescription, GenerationError, Image};
+
+/// Every loaded pattern texture plus the banner base, in a stable order.
+///
+/// The index of a pattern is its id everywhere else (`layers`, seeds etc).
+#[derive(Debug, Clone)]
+pub struct PatternRegistry {
+    patterns: Vec<(String, Image)>,
+    base: Image,
+}
+
+impl PatternRegistry {
+    /// Patterns get sorted by id so the same set always has the same order
+    pub fn new(mut patterns: Vec<(String, Image)>) -> Result<Self> {
+        patterns.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(PatternRegistry {
+            patterns,
+            base: Banner::load_base()?,
+        })
+    }
+
+    /// Loads every image in `dir`, the file name (without extension) is the pattern id
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let pattern_files = read_dir(dir.as_ref())?;
+        let mut patterns = Vec::new();
+
+        for file in pattern_files {
+            let file = file?;
+            let id = file
+                .file_name()
+                .to_string_lossy()
+                .split_once('.')
+                .unwrap()
+                .0
+                .to_string();
+
+            // this excludes the .gitkeep file
+            if id.is_empty() {
+                continue;
+            }
+
+            let img = ImageReader::open(file.path())?.decode()?.to_rgba8();
+
+            patterns.push((id, img));
+        }
+
+        Self::new(patterns)
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Every `(id, texture)`, indexed by pattern id
+    pub fn patterns(&self) -> &[(String, Image)] {
+        &self.patterns
+    }
+
+    /// The name of a pattern, like `creeper`
+    pub fn name(&self, pattern_id: usize) -> Option<&str> {
+        self.patterns.get(pattern_id).map(|p| p.0.as_str())
+    }
+
+    /// The id of a pattern by its name, like `creeper`
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.patterns
+            .binary_search_by(|p| p.0.as_str().cmp(name))
+            .ok()
+    }
+
+    /// Makes sure every pattern id actually exists
+    pub fn check_layers(&self, layers: &[(usize, Color)]) -> Result<(), GenerationError> {
+        match layers.iter().find(|(id, _)| *id >= self.len()) {
+            Some((id, _)) => Err(GenerationError::UnknownPattern {
+                id: *id,
+                len: self.len(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Renders a banner at its native `20x40`
+    pub fn render(&self, banner: &BannerDescription) -> Result<Image> {
+        self.check_layers(&banner.layers)?;
+
+        let mut banner_img = Banner::new(&mut self.base.clone(), banner.base)?;
+        for (pattern_id, color) in &banner.layers {
+            let pattern = Pattern::new(self.patterns[*pattern_id].1.clone());
+            banner_img.add_pattern(pattern, color)?;
+        }
+
+        Ok(banner_img.img_owned())
+    }
+
+    /// The same banner without any layers that don't change how it looks, see [`canonical_layers`]
+    pub fn canonicalize(&self, banner: &BannerDescription) -> Result<BannerDescription> {
+        let kept = self.visible_layers(banner)?;
+
+        Ok(BannerDescription {
+            base: banner.base,
+            layers: kept.into_iter().map(|i| banner.layers[i]).collect(),
+        })
+    }
+
+    /// Indexes of the layers [`PatternRegistry::canonicalize`] keeps, in order
+    pub fn visible_layers(&self, banner: &BannerDescription) -> Result<Vec<usize>> {
+        self.check_layers(&banner.layers)?;
+
+        let patterns = banner
+            .layers
+            .iter()
+            .map(|(pattern_id, _)| Pattern::new(self.patterns[*pattern_id].1.clone()))
+            .collect::<Vec<_>>();
+        let layers = patterns
+            .iter()
+            .zip(&banner.layers)
+            .map(|(pattern, (_, color))| (pattern, *color))
+            .collect::<Vec<_>>();
+        Ok(canonical_layers(&self.base, banner.base, &layers)?)
+    }
+
+    /// If two banners render to the exact same pixels
+    pub fn identical(&self, a: &BannerDescription, b: &BannerDescription) -> Result<bool> {
+        if a == b {
+            return Ok(true);
+        }
+        Ok(self.render(a)? == self.render(b)?)
+    }
+
+    /// Renders banners next to each other, left to right
+    pub fn render_row(&self, banners: &[BannerDescription]) -> Result<Image> {
+        let mut row = Image::new(Banner::X * banners.len() as u32, Banner::Y);
+        for (i, banner) in banners.iter().enumerate() {
+            let banner = self.render(banner)?;
+            imageops::replace(&mut row, &banner, (Banner::X * i as u32).into(), 0);
+        }
+
+        Ok(row)
+    }
+}
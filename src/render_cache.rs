@@ -0,0 +1,114 @@
+use axum::body::Bytes;
+use lru::LruCache;
+use mcb::{Banner, Color};
+use minecraft_banners::BannerDescription;
+use std::sync::{
+    Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+
+/// Everything that decides what an encoded banner looks like.
+///
+/// Seeds, query arguments etc all boil down to this, so `/banner/{seed}` and `/create`
+/// share entries whenever they end up with the same banner.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BannerKey {
+    pub banner: BannerDescription,
+    /// `None` for the native `20x40` size
+    pub width: Option<u32>,
+    pub format: &'static str,
+}
+
+impl BannerKey {
+    pub fn new(banner: BannerDescription, width: Option<u32>, format: &'static str) -> Self {
+        BannerKey {
+            banner,
+            // resizing to the same size is the same image
+            width: width.filter(|w| *w != Banner::X),
+            format,
+        }
+    }
+}
+
+/// Rough amount of memory an entry takes up, used for the byte limit
+fn entry_size(key: &BannerKey, bytes: &Bytes) -> usize {
+    bytes.len() + key.banner.layers.len() * size_of::<(usize, Color)>() + size_of::<BannerKey>()
+}
+
+#[derive(Debug)]
+struct Entries {
+    lru: LruCache<BannerKey, Bytes>,
+    bytes: usize,
+}
+
+/// A bounded LRU cache of encoded banners, limited by both entry count and total bytes.
+#[derive(Debug)]
+pub struct RenderCache {
+    entries: Mutex<Entries>,
+    max_entries: usize,
+    max_bytes: usize,
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
+    pub evictions: AtomicU64,
+}
+
+impl RenderCache {
+    /// A `max_entries` or `max_bytes` of 0 disables the cache.
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        RenderCache {
+            entries: Mutex::new(Entries {
+                lru: LruCache::unbounded(),
+                bytes: 0,
+            }),
+            max_entries,
+            max_bytes,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.max_entries > 0 && self.max_bytes > 0
+    }
+
+    pub fn get(&self, key: &BannerKey) -> Option<Bytes> {
+        if !self.enabled() {
+            return None;
+        }
+
+        let bytes = self.entries.lock().unwrap().lru.get(key).cloned();
+        match bytes {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        bytes
+    }
+
+    pub fn insert(&self, key: BannerKey, bytes: Bytes) {
+        let size = entry_size(&key, &bytes);
+        if !self.enabled() || size > self.max_bytes {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(old) = entries.lru.put(key.clone(), bytes) {
+            entries.bytes -= entry_size(&key, &old);
+        }
+        entries.bytes += size;
+
+        while entries.lru.len() > self.max_entries || entries.bytes > self.max_bytes {
+            let Some((key, bytes)) = entries.lru.pop_lru() else {
+                break;
+            };
+            entries.bytes -= entry_size(&key, &bytes);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns how many entries & bytes are currently cached
+    pub fn usage(&self) -> (usize, usize) {
+        let entries = self.entries.lock().unwrap();
+        (entries.lru.len(), entries.bytes)
+    }
+}
@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// every JSON body the api returns
+// seeds & combinations are strings since they go way past what javascript numbers can represent
+
+/// A generated list of patterns, returned by `/pattern` & `/pattern/{seed}`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PatternListResponse {
+    /// The seed the patterns were generated from
+    #[schema(example = "1234567890")]
+    pub seed: String,
+    /// The generator version the seed was generated with
+    #[schema(example = "v1")]
+    pub version: String,
+    /// Hex color of the banner base
+    #[schema(example = "#F9FFFE")]
+    pub base: String,
+    /// `[pattern id, hex color]` for every layer, bottom to top
+    #[schema(value_type = Vec<Vec<String>>, example = json!([["creeper", "#1D1D21"]]))]
+    pub patterns: Vec<(String, String)>,
+}
+
+/// Everything that can go on a banner, returned by `/metadata`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MetadataResponse {
+    /// Every pattern id, the index is the id used in `layers`
+    pub patterns: Vec<String>,
+    /// Every hex color, the index is the id used in `base_color` & `layers`
+    pub colors: Vec<String>,
+    /// How many banners are possible, also the upper bound for seeds
+    #[schema(example = "1475646641940097552")]
+    pub combinations: String,
+    /// How many banners have exactly `n` layers, indexed by `n`
+    #[schema(example = json!(["16", "10752", "7225344"]))]
+    pub combinations_per_layer: Vec<String>,
+    /// Upper bound of the banners that actually look different, leaving out layers that are covered up.
+    /// Only there when it's been precomputed for the loaded patterns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "94143178827")]
+    pub distinct_combinations: Option<String>,
+    /// Same as `distinct_combinations`, but for banners with exactly `n` visible layers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = json!(["16", "10752", "6938112"]))]
+    pub distinct_combinations_per_layer: Option<Vec<String>>,
+    /// Weighted generation profiles `profile=` accepts, sorted
+    #[schema(example = json!(["calm"]))]
+    pub profiles: Vec<String>,
+}
+
+/// The closest banner to an uploaded image, returned by `/solve`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SolveResponse {
+    /// Hex color of the banner base
+    #[schema(example = "#F9FFFE")]
+    pub base: String,
+    /// `[pattern id, hex color]` for every layer, bottom to top
+    #[schema(value_type = Vec<Vec<String>>, example = json!([["creeper", "#1D1D21"]]))]
+    pub patterns: Vec<(String, String)>,
+    /// Average color difference per pixel (CIE76), `0` is a perfect match, around `2.3` is barely noticeable
+    #[schema(example = 4.2)]
+    pub error: f32,
+    /// The `/create` url rendering the banner.
+    /// Solved banners don't come from a seed, so this is their permanent link
+    #[schema(example = "/create?base_color=0&layers=[7,15]")]
+    pub preview: String,
+}
+
+/// The banners spelling out a text, returned by `/text/recipes`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TextResponse {
+    /// One banner per character, left to right
+    pub letters: Vec<LetterResponse>,
+}
+
+/// The banner for a single character
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LetterResponse {
+    #[schema(example = "A")]
+    pub character: String,
+    /// Hex color of the banner base
+    #[schema(example = "#F9FFFE")]
+    pub base: String,
+    /// `[pattern id, hex color]` for every layer, bottom to top
+    #[schema(value_type = Vec<Vec<String>>, example = json!([["stripe_left", "#1D1D21"]]))]
+    pub patterns: Vec<(String, String)>,
+    /// The `/create` url rendering just this banner
+    #[schema(example = "/create?base_color=0&layers=[34,15]")]
+    pub preview: String,
+}
+
+/// If two banners look the same, returned by `/compare`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CompareResponse {
+    /// If both render to the exact same pixels
+    pub identical: bool,
+    pub a: CanonicalResponse,
+    pub b: CanonicalResponse,
+}
+
+/// A banner without the layers that don't change how it looks
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CanonicalResponse {
+    /// Hex color of the banner base
+    #[schema(example = "#F9FFFE")]
+    pub base: String,
+    /// `[pattern id, hex color]` for every layer that shows, bottom to top
+    #[schema(value_type = Vec<Vec<String>>, example = json!([["creeper", "#1D1D21"]]))]
+    pub patterns: Vec<(String, String)>,
+    /// How many layers were left out, covered up or not changing anything
+    pub hidden: usize,
+    /// The `/create` url rendering the banner
+    #[schema(example = "/create?base_color=0&layers=[7,15]")]
+    pub preview: String,
+}
+
+/// Banners that look like a seeded one, returned by `/similar/{seed}`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SimilarResponse {
+    /// The seed that was searched for, pinned to its version
+    #[schema(example = "v2-1234567890")]
+    pub seed: String,
+    /// The closest banners first
+    pub similar: Vec<SimilarBanner>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SimilarBanner {
+    /// The seed it's generated from, pinned to its version
+    #[schema(example = "v2-1234567890")]
+    pub seed: String,
+    /// The `/banner/{seed}` url rendering it, with the overrides that were changed if any
+    #[schema(example = "/banner/v2-1234567890?layers=&layers=[7,15]")]
+    pub url: String,
+    /// How different it looks, `0` is the same & `1` has nothing in common
+    #[schema(example = 0.12)]
+    pub distance: f32,
+    /// Hex color of the banner base
+    #[schema(example = "#F9FFFE")]
+    pub base: String,
+    /// `[pattern id, hex color]` for every layer, bottom to top
+    #[schema(value_type = Vec<Vec<String>>, example = json!([["creeper", "#1D1D21"]]))]
+    pub patterns: Vec<(String, String)>,
+}
+
+/// The colors of a banner, returned by `/analyze/{seed}`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AnalyzeResponse {
+    /// The seed that was analyzed, pinned to its version
+    #[schema(example = "v2-1234567890")]
+    pub seed: String,
+    /// Every dye that shows & how much of the banner it covers, the most first
+    pub coverage: Vec<ColorCoverage>,
+    /// Hex colors of the (up to 3) dyes covering the most
+    #[schema(example = json!(["#1D1D21", "#F9FFFE"]))]
+    pub dominant: Vec<String>,
+    /// Average color of the rendered banner, shading included
+    pub average_lab: LabColor,
+    /// Every layer against the dye it's mostly on top of, bottom to top
+    pub layers: Vec<LayerAnalysis>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ColorCoverage {
+    /// Dye name
+    #[schema(example = "light_blue")]
+    pub color: String,
+    #[schema(example = "#3AB3DA")]
+    pub hex: String,
+    /// Share of the banner, from `0` to `1`
+    #[schema(example = 0.42)]
+    pub coverage: f32,
+}
+
+/// A CIELAB color (D65)
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LabColor {
+    /// Lightness, `0` to `100`
+    #[schema(example = 54.2)]
+    pub l: f32,
+    #[schema(example = -12.5)]
+    pub a: f32,
+    #[schema(example = 30.1)]
+    pub b: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LayerAnalysis {
+    #[schema(example = "creeper")]
+    pub pattern: String,
+    /// Hex color of the layer
+    #[schema(example = "#1D1D21")]
+    pub color: String,
+    /// Hex color of the dye showing the most beneath the pattern
+    #[schema(example = "#F9FFFE")]
+    pub below: String,
+    /// WCAG contrast ratio between the two, from `1` (invisible) to `21`
+    #[schema(example = 17.4)]
+    pub contrast: f32,
+}
+
+/// A seeded banner in heraldry, returned by `/blazon/{seed}`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BlazonResponse {
+    /// The seed that was used, pinned to its version
+    #[schema(example = "v2-1234567890")]
+    pub seed: String,
+    /// The field first, then every layer bottom to top
+    #[schema(example = "Lime, a creeper charge sable, a bordure gules")]
+    pub blazon: String,
+}
+
+/// Returned by `/readyz`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReadyResponse {
+    /// If the server should receive traffic
+    pub ready: bool,
+    /// How many patterns were loaded
+    pub patterns: usize,
+}
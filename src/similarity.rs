@@ -0,0 +1,218 @@
+//! Finding banners that look like another one.
+//!
+//! Banners are compared by [`Features`]: how much of each kind of color they show (in [`Lab`])
+//! and which patterns are visible. Neighbors come from tweaking one thing of the seeded banner
+//! at a time, with the same overrides `/banner/{seed}` takes, plus a bunch of other seeds.
+
+use image::imageops::{self, FilterType};
+use mcb::{Banner, Color};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+use crate::{
+    BannerDescription, BannerGenerator, GenerateOptions, Image, Lab, PatternRegistry, Seed,
+};
+
+/// Buckets for lightness, `a` & `b` each get [`AB_BINS`]
+const L_BINS: usize = 4;
+const AB_BINS: usize = 4;
+const BINS: usize = L_BINS * AB_BINS * AB_BINS;
+/// `a` & `b` are clamped to `-AB_RANGE..AB_RANGE`, no dye goes further
+const AB_RANGE: f32 = 100.0;
+/// How much colors count compared to patterns
+const COLOR_WEIGHT: f32 = 0.6;
+/// Other seeds mixed in with the tweaked ones
+const RANDOM_CANDIDATES: usize = 256;
+
+/// What a banner looks like, compared with [`Features::distance`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Features {
+    /// How much of the banner falls in each `(l, a, b)` bucket, sums up to 1
+    colors: [f32; BINS],
+    /// Every pattern that shows, sorted
+    patterns: Vec<usize>,
+}
+
+impl Features {
+    pub fn new(registry: &PatternRegistry, banner: &BannerDescription) -> anyhow::Result<Self> {
+        Self::from_render(registry, banner, &registry.render(banner)?)
+    }
+
+    /// Same as [`Features::new`] for a banner that's already rendered
+    fn from_render(
+        registry: &PatternRegistry,
+        banner: &BannerDescription,
+        render: &Image,
+    ) -> anyhow::Result<Self> {
+        let mut patterns = registry
+            .canonicalize(banner)?
+            .layers
+            .into_iter()
+            .map(|(pattern_id, _)| pattern_id)
+            .collect::<Vec<_>>();
+        patterns.sort_unstable();
+        patterns.dedup();
+
+        Ok(Features {
+            colors: histogram(render),
+            patterns,
+        })
+    }
+
+    /// `0` for banners that look the same, up to `1` for nothing in common
+    pub fn distance(&self, other: &Features) -> f32 {
+        let colors = self
+            .colors
+            .iter()
+            .zip(&other.colors)
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f32>()
+            / 2.0;
+
+        // jaccard distance of the visible patterns
+        let shared = self
+            .patterns
+            .iter()
+            .filter(|p| other.patterns.binary_search(p).is_ok())
+            .count();
+        let all = self.patterns.len() + other.patterns.len() - shared;
+        let patterns = match all {
+            0 => 0.0,
+            all => 1.0 - shared as f32 / all as f32,
+        };
+
+        COLOR_WEIGHT * colors + (1.0 - COLOR_WEIGHT) * patterns
+    }
+}
+
+/// Color histogram of the banner at half size, so single stray pixels don't matter much
+fn histogram(render: &Image) -> [f32; BINS] {
+    let small = imageops::resize(render, Banner::X / 2, Banner::Y / 2, FilterType::Triangle);
+    let bin = |value: f32, min: f32, max: f32, bins: usize| {
+        let t = ((value - min) / (max - min)).clamp(0.0, 1.0);
+        ((t * bins as f32) as usize).min(bins - 1)
+    };
+
+    let mut colors = [0.0; BINS];
+    let mut total = 0.0;
+    for pixel in small.pixels() {
+        let lab = Lab::from_rgb((pixel[0], pixel[1], pixel[2]));
+        let l = bin(lab.l, 0.0, 100.0, L_BINS);
+        let a = bin(lab.a, -AB_RANGE, AB_RANGE, AB_BINS);
+        let b = bin(lab.b, -AB_RANGE, AB_RANGE, AB_BINS);
+
+        let weight = pixel[3] as f32 / 255.0;
+        colors[(l * AB_BINS + a) * AB_BINS + b] += weight;
+        total += weight;
+    }
+
+    if total > 0.0 {
+        colors.iter_mut().for_each(|c| *c /= total);
+    }
+    colors
+}
+
+/// A banner close to the one searched for, generated from `seed` with `options`
+#[derive(Debug, Clone)]
+pub struct Neighbor {
+    pub seed: Seed,
+    /// Empty for plain seeds, otherwise the one thing that was changed
+    pub options: GenerateOptions,
+    pub banner: BannerDescription,
+    /// See [`Features::distance`]
+    pub distance: f32,
+}
+
+/// Up to `count` banners that look the most like the one of `seed`, closest first.
+///
+/// Never includes banners that render exactly like it, the same seed always gives the same neighbors.
+pub fn similar(
+    generator: &BannerGenerator,
+    seed: Seed,
+    count: usize,
+) -> anyhow::Result<Vec<Neighbor>> {
+    let registry = generator.registry();
+    let (_, target) = generator.generate(Some(seed), GenerateOptions::default())?;
+    let target_render = registry.render(&target)?;
+    let features = Features::from_render(registry, &target, &target_render)?;
+
+    let mut candidates = tweaks(registry.len(), &target)
+        .into_iter()
+        .map(|options| (seed, options))
+        .collect::<Vec<_>>();
+
+    // same rng for the same seed, so the neighbors don't change between requests
+    let mut bytes = [0; 32];
+    bytes[..16].copy_from_slice(&seed.to_le_bytes());
+    let mut rng = ChaCha8Rng::from_seed(bytes);
+    let total = generator.combinations()?.total;
+    for _ in 0..RANDOM_CANDIDATES {
+        candidates.push((rng.random_range(0..total), GenerateOptions::default()));
+    }
+
+    let mut seen = HashSet::from([target_render.into_raw()]);
+    let mut neighbors = Vec::new();
+    for (seed, options) in candidates {
+        let (_, banner) = generator.generate(Some(seed), options.clone())?;
+        let render = registry.render(&banner)?;
+        if seen.contains(render.as_raw()) {
+            continue;
+        }
+        let distance = features.distance(&Features::from_render(registry, &banner, &render)?);
+        seen.insert(render.into_raw());
+
+        neighbors.push(Neighbor {
+            seed,
+            options,
+            banner,
+            distance,
+        });
+    }
+
+    // stable, so ties keep the tweaks first
+    neighbors.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    neighbors.truncate(count);
+    Ok(neighbors)
+}
+
+/// Every way to change one thing about `banner` with [`GenerateOptions`]:
+/// the base color, cutting off layers, or one layer's pattern or color
+fn tweaks(pattern_len: usize, banner: &BannerDescription) -> Vec<GenerateOptions> {
+    let mut tweaks = Vec::new();
+
+    for color in Color::all().iter().filter(|c| **c != banner.base) {
+        tweaks.push(GenerateOptions {
+            base_color: Some(*color),
+            ..Default::default()
+        });
+    }
+
+    for max_layers in 0..banner.layers.len() {
+        tweaks.push(GenerateOptions {
+            max_layers: Some(max_layers),
+            ..Default::default()
+        });
+    }
+
+    for (i, (pattern_id, color)) in banner.layers.iter().enumerate() {
+        // both halves are always set, v1 treats a lone pattern override differently
+        let replace = |layer: (usize, Color)| {
+            let mut layers = vec![None; i];
+            layers.push(Some((Some(layer.0), Some(layer.1))));
+            GenerateOptions {
+                layers,
+                ..Default::default()
+            }
+        };
+
+        for other in (0..pattern_len).filter(|p| p != pattern_id) {
+            tweaks.push(replace((other, *color)));
+        }
+        for other in Color::all().iter().filter(|c| *c != color) {
+            tweaks.push(replace((*pattern_id, *other)));
+        }
+    }
+
+    tweaks
+}
@@ -0,0 +1,347 @@
+//! Finds the banner that looks the most like an image.
+//!
+//! Every pattern gets tinted in every color once, then a beam search stacks layers
+//! on the best base colors, keeping the best few partial banners after every layer.
+//! The winner gets refined by swapping single layers (and the base) for better ones.
+//! How close a banner is gets measured per pixel in [`Lab`], so it's the difference people see.
+
+use image::imageops::{self, FilterType};
+use mcb::{
+    Banner, Color, Pattern,
+    composite::{self, BYTES, PIXELS},
+};
+
+use crate::{BannerDescription, Image, PatternRegistry, lab::Lab};
+
+/// How often [`Solver::solve`] goes over every layer looking for a better one
+const REFINE_PASSES: usize = 3;
+
+/// How much a [`Solver`] searches
+#[derive(Debug, Clone, Copy)]
+pub struct SolveOptions {
+    /// At most this many layers, capped to [`Banner::VANILLA_MAX_PATTERN_SIZE`]
+    pub max_layers: usize,
+    /// How many of the best partial banners are kept after every layer, `1` is a plain greedy search
+    pub beam_width: usize,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        SolveOptions {
+            max_layers: Banner::VANILLA_MAX_PATTERN_SIZE,
+            beam_width: 4,
+        }
+    }
+}
+
+/// The closest banner a [`Solver`] found
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub banner: BannerDescription,
+    /// Average color difference (CIE76) per pixel, `0` is a perfect match
+    pub error: f32,
+}
+
+/// A pattern tinted with a color, ready to be composited
+#[derive(Debug)]
+struct Layer {
+    pattern: usize,
+    color: Color,
+    raw: Box<[u8; BYTES]>,
+    /// Every pixel the layer can change (alpha above 0)
+    pixels: Vec<usize>,
+}
+
+/// Approximates images with banners made from a [`PatternRegistry`]
+#[derive(Debug)]
+pub struct Solver {
+    /// The plain banner for every [`Color`], indexed by color id
+    bases: Vec<[u8; BYTES]>,
+    /// Every pattern in every color, see [`Solver::layer`] for the order
+    layers: Vec<Layer>,
+}
+
+impl Solver {
+    /// Tints every pattern of the registry in every color up front
+    pub fn new(registry: &PatternRegistry) -> anyhow::Result<Self> {
+        let mut bases = Vec::with_capacity(Color::all().len());
+        for color in Color::all() {
+            let base = registry.render(&BannerDescription {
+                base: *color,
+                layers: Vec::new(),
+            })?;
+            bases.push(*to_raw(base)?);
+        }
+
+        let mut layers = Vec::with_capacity(registry.len() * Color::all().len());
+        for (pattern, (_, texture)) in registry.patterns().iter().enumerate() {
+            let texture = Pattern::new(texture.clone()).img_owned();
+            let pixels = texture
+                .pixels()
+                .enumerate()
+                .filter(|(_, pixel)| pixel[3] > 0)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+
+            for color in Color::all() {
+                let mut raw = to_raw(texture.clone())?;
+                composite::tint(&mut raw[..], color.rgb());
+                layers.push(Layer {
+                    pattern,
+                    color: *color,
+                    raw,
+                    pixels: pixels.clone(),
+                });
+            }
+        }
+
+        Ok(Solver { bases, layers })
+    }
+
+    /// Finds the banner closest to `target`, which gets stretched to `20x40` if it isn't already
+    pub fn solve(&self, target: &Image, options: SolveOptions) -> Solution {
+        let target = Target::new(target);
+        let max_layers = options.max_layers.min(Banner::VANILLA_MAX_PATTERN_SIZE);
+        let beam_width = options.beam_width.max(1);
+
+        let mut beam = Color::all()
+            .iter()
+            .map(|color| State::new(&target, &self.bases[*color as usize], *color))
+            .collect::<Vec<_>>();
+        beam.sort_by(|a, b| a.error.total_cmp(&b.error));
+        beam.truncate(beam_width);
+        let mut best = beam[0].clone();
+
+        let mut scratch = Box::new([0; BYTES]);
+        for _ in 0..max_layers {
+            let mut expansions = Vec::with_capacity(beam.len() * self.layers.len());
+            for (i, state) in beam.iter().enumerate() {
+                for (j, layer) in self.layers.iter().enumerate() {
+                    let error = state.error_with(&target, layer, &mut scratch);
+                    expansions.push((error, i, j));
+                }
+            }
+            if expansions.is_empty() {
+                break;
+            }
+
+            expansions.sort_by(|a, b| a.0.total_cmp(&b.0));
+            expansions.truncate(beam_width);
+            beam = expansions
+                .into_iter()
+                .map(|(_, i, j)| beam[i].with_layer(&target, &self.layers[j]))
+                .collect();
+
+            if beam[0].error < best.error {
+                best = beam[0].clone();
+            }
+        }
+
+        let best = self.refine(&target, best.banner, max_layers);
+        Solution {
+            banner: best.banner,
+            error: best.error / target.weight,
+        }
+    }
+
+    /// Swaps, removes or inserts single layers (or swaps the base color) until nothing improves.
+    ///
+    /// Beam search only ever adds layers on top, this catches the earlier picks that the
+    /// later ones made worse & layers that only help underneath others.
+    fn refine(&self, target: &Target, banner: BannerDescription, max_layers: usize) -> State {
+        let mut best = self.render(target, banner);
+        let mut scratch = Box::new([0; BYTES]);
+
+        for _ in 0..REFINE_PASSES {
+            let mut candidates = Vec::new();
+            for color in Color::all() {
+                candidates.push(BannerDescription {
+                    base: *color,
+                    layers: best.banner.layers.clone(),
+                });
+            }
+            for i in 0..best.banner.layers.len() {
+                let mut removed = best.banner.clone();
+                removed.layers.remove(i);
+                candidates.push(removed);
+
+                for layer in &self.layers {
+                    let mut swapped = best.banner.clone();
+                    swapped.layers[i] = (layer.pattern, layer.color);
+                    candidates.push(swapped);
+                }
+            }
+            if best.banner.layers.len() < max_layers {
+                for i in 0..=best.banner.layers.len() {
+                    for layer in &self.layers {
+                        let mut inserted = best.banner.clone();
+                        inserted.layers.insert(i, (layer.pattern, layer.color));
+                        candidates.push(inserted);
+                    }
+                }
+            }
+
+            let mut improved = false;
+            for candidate in candidates {
+                let error = best.error_of(target, self.composite(&candidate, &mut scratch));
+                if error < best.error {
+                    best = self.render(target, candidate);
+                    improved = true;
+                }
+            }
+            if !improved {
+                break;
+            }
+        }
+
+        best
+    }
+
+    fn render(&self, target: &Target, banner: BannerDescription) -> State {
+        let mut raw = Box::new([0; BYTES]);
+        self.composite(&banner, &mut raw);
+        State::from_raw(target, raw, banner)
+    }
+
+    /// Composites `banner` into `raw`, returning it
+    fn composite<'a>(
+        &self,
+        banner: &BannerDescription,
+        raw: &'a mut [u8; BYTES],
+    ) -> &'a [u8; BYTES] {
+        raw.copy_from_slice(&self.bases[banner.base as usize][..]);
+        for (pattern, color) in &banner.layers {
+            composite::overlay(raw, &self.layer(*pattern, *color).raw);
+        }
+        raw
+    }
+
+    fn layer(&self, pattern: usize, color: Color) -> &Layer {
+        &self.layers[pattern * Color::all().len() + color as usize]
+    }
+}
+
+/// The image being solved for, as [`Lab`] colors
+struct Target {
+    colors: Vec<Lab>,
+    /// How much each pixel counts, transparent pixels don't
+    weights: Vec<f32>,
+    weight: f32,
+}
+
+impl Target {
+    fn new(image: &Image) -> Self {
+        let resized;
+        let image = if image.dimensions() == (Banner::X, Banner::Y) {
+            image
+        } else {
+            resized = imageops::resize(image, Banner::X, Banner::Y, FilterType::Triangle);
+            &resized
+        };
+
+        let colors = image
+            .pixels()
+            .map(|p| Lab::from_rgb((p[0], p[1], p[2])))
+            .collect();
+        let weights = image
+            .pixels()
+            .map(|p| p[3] as f32 / 255.0)
+            .collect::<Vec<_>>();
+        // a fully transparent image matches anything, no need to divide by 0
+        let weight = weights.iter().sum::<f32>().max(1.0);
+
+        Target {
+            colors,
+            weights,
+            weight,
+        }
+    }
+
+    /// The weighted difference of pixel `i`
+    fn error(&self, raw: &[u8; BYTES], i: usize) -> f32 {
+        if self.weights[i] == 0.0 {
+            return 0.0;
+        }
+        let pixel = &raw[i * 4..i * 4 + 3];
+        let color = Lab::from_rgb((pixel[0], pixel[1], pixel[2]));
+        self.weights[i] * self.colors[i].delta_e(&color)
+    }
+}
+
+/// A (partial) banner with the error of every pixel
+#[derive(Clone)]
+struct State {
+    raw: Box<[u8; BYTES]>,
+    errors: Vec<f32>,
+    /// Sum of `errors`
+    error: f32,
+    banner: BannerDescription,
+}
+
+impl State {
+    fn new(target: &Target, base: &[u8; BYTES], color: Color) -> Self {
+        let banner = BannerDescription {
+            base: color,
+            layers: Vec::new(),
+        };
+        State::from_raw(target, Box::new(*base), banner)
+    }
+
+    fn from_raw(target: &Target, raw: Box<[u8; BYTES]>, banner: BannerDescription) -> Self {
+        let errors = (0..PIXELS)
+            .map(|i| target.error(&raw, i))
+            .collect::<Vec<_>>();
+        State {
+            error: errors.iter().sum(),
+            raw,
+            errors,
+            banner,
+        }
+    }
+
+    /// The error after adding `layer` on top, only looking at the pixels it covers
+    fn error_with(&self, target: &Target, layer: &Layer, scratch: &mut [u8; BYTES]) -> f32 {
+        scratch.copy_from_slice(&self.raw[..]);
+        composite::overlay(scratch, &layer.raw);
+
+        let mut error = self.error;
+        for &i in &layer.pixels {
+            error += target.error(scratch, i) - self.errors[i];
+        }
+        error
+    }
+
+    fn with_layer(&self, target: &Target, layer: &Layer) -> State {
+        let mut state = self.clone();
+        composite::overlay(&mut state.raw, &layer.raw);
+        for &i in &layer.pixels {
+            state.errors[i] = target.error(&state.raw, i);
+        }
+        state.error = state.errors.iter().sum();
+        state.banner.layers.push((layer.pattern, layer.color));
+        state
+    }
+
+    /// The error of a whole other banner, only recomputing pixels that differ from this one
+    fn error_of(&self, target: &Target, raw: &[u8; BYTES]) -> f32 {
+        let mut error = 0.0;
+        for i in 0..PIXELS {
+            let pixel = i * 4..i * 4 + 3;
+            error += if raw[pixel.clone()] == self.raw[pixel] {
+                self.errors[i]
+            } else {
+                target.error(raw, i)
+            };
+        }
+        error
+    }
+}
+
+/// Banner sized images as fixed size arrays, for [`composite`]
+fn to_raw(image: Image) -> Result<Box<[u8; BYTES]>, mcb::Error> {
+    image
+        .into_raw()
+        .into_boxed_slice()
+        .try_into()
+        .map_err(|_| mcb::Error::InvalidBannerDimension)
+}
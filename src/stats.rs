@@ -0,0 +1,90 @@
+use anyhow::Result;
+use std::{
+    fs::{File, read_to_string, rename},
+    io::Write,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+const BANNER_STAT_FILE: &str = "count.txt";
+const BANNER_STAT_TMP_FILE: &str = "count.txt.tmp";
+/// How often the banner count gets written to disk
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How many banners have been generated, ever.
+///
+/// Incremented on every render without any locking,
+/// a background task (see [`spawn_flush_task`]) persists it to disk.
+#[derive(Debug)]
+pub struct BannerCounter {
+    count: AtomicU64,
+    /// The last count that was written to disk, to skip pointless writes
+    saved: AtomicU64,
+    /// Only held while writing to disk so two saves never share the temp file
+    write_lock: Mutex<()>,
+}
+
+impl BannerCounter {
+    pub fn load() -> Result<Self> {
+        let data = read_to_string(BANNER_STAT_FILE).unwrap_or("0".to_string());
+        let count = data.trim().parse::<u64>()?;
+
+        Ok(BannerCounter {
+            count: AtomicU64::new(count),
+            saved: AtomicU64::new(count),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    pub fn increment(&self) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Writes the current count to disk if it has changed since the last save.
+    ///
+    /// Writes to a temp file first and then renames it over the real one,
+    /// so a crash mid-write can't leave a half written `count.txt`.
+    pub fn save(&self) -> Result<()> {
+        let _lock = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let count = self.get();
+        if self.saved.load(Ordering::Relaxed) == count {
+            return Ok(());
+        }
+
+        let mut file = File::create(BANNER_STAT_TMP_FILE)?;
+        file.write_all(count.to_string().as_bytes())?;
+        file.sync_all()?;
+        rename(BANNER_STAT_TMP_FILE, BANNER_STAT_FILE)?;
+
+        self.saved.store(count, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Periodically saves the [`BannerCounter`] in the background.
+///
+/// This doesn't do the final save on shutdown, call [`BannerCounter::save`] for that.
+pub fn spawn_flush_task(counter: Arc<BannerCounter>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let counter = counter.clone();
+            // ignore error
+            match tokio::task::spawn_blocking(move || counter.save()).await {
+                Ok(Err(e)) => tracing::error!("Failed to save the banner count: {e:#}"),
+                Err(e) => tracing::error!("Banner count flush task panicked: {e}"),
+                Ok(Ok(_)) => (),
+            }
+        }
+    })
+}
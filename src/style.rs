@@ -0,0 +1,151 @@
+//! How random banners are picked, plain random or tweaked to look good.
+//!
+//! Plain random banners are often mud: a black layer on black, or a layer hidden under the
+//! ones above it. The pretty style starts from the exact same banner as the plain one & only
+//! redraws (or drops) the layers that don't stand out, so the same seed still looks roughly the same.
+
+use mcb::Color;
+use rand_chacha::ChaCha8Rng;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+use crate::{BannerDescription, PatternRegistry, palette::analyze};
+
+/// Layers need at least this WCAG contrast ratio against what's beneath them.
+/// Two of the same dye have `1`, black on white around `17`
+pub const MIN_CONTRAST: f32 = 1.5;
+/// How often a layer gets redrawn before settling for the best one so far
+const MAX_REDRAWS: usize = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Style {
+    /// Every banner is as likely as any other
+    #[default]
+    Random,
+    /// Every layer stands out from what it's on top of & nothing is hidden, see [`score`]
+    Pretty,
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Style::Random => write!(f, "random"),
+            Style::Pretty => write!(f, "pretty"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown style, expected random or pretty")]
+pub struct ParseStyleError;
+
+impl FromStr for Style {
+    type Err = ParseStyleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "random" => Ok(Style::Random),
+            "pretty" => Ok(Style::Pretty),
+            _ => Err(ParseStyleError),
+        }
+    }
+}
+
+/// How good a banner looks, from `0` to `1`: the share of layers that show & stand out.
+///
+/// A layer counts when the layers above don't hide it & its contrast against the dye it's mostly
+/// on top of is at least [`MIN_CONTRAST`], so a banner without any layers is a `1`.
+pub fn score(registry: &PatternRegistry, banner: &BannerDescription) -> anyhow::Result<f32> {
+    if banner.layers.is_empty() {
+        return Ok(1.0);
+    }
+
+    let visible = registry.canonicalize(banner)?.layers;
+    let palette = analyze(registry, banner)?;
+
+    // canonical layers keep their order, so walk both together
+    let mut shown = visible.iter().peekable();
+    let mut good = 0;
+    for (layer, contrast) in banner.layers.iter().zip(&palette.layers) {
+        if shown.peek() == Some(&layer) {
+            shown.next();
+            if contrast.contrast >= MIN_CONTRAST {
+                good += 1;
+            }
+        }
+    }
+
+    Ok(good as f32 / banner.layers.len() as f32)
+}
+
+/// Redraws every layer that blends into what's beneath it or hides a layer below it, layers
+/// that don't get there in [`MAX_REDRAWS`] are dropped.
+///
+/// `fixed` layers (overridden ones) are never redrawn or dropped & neither is anything below
+/// them, so every override stays at its index. New layers come from `draw`.
+pub(crate) fn prettify(
+    registry: &PatternRegistry,
+    banner: BannerDescription,
+    fixed: &[bool],
+    mut draw: impl FnMut(&mut ChaCha8Rng) -> (usize, Color),
+    rng: &mut ChaCha8Rng,
+) -> anyhow::Result<BannerDescription> {
+    // nothing gets dropped before the last override, so indexes match up to there
+    let last_fixed = fixed.iter().rposition(|fixed| *fixed);
+    let droppable = |i: usize| last_fixed.is_none_or(|last| i > last);
+
+    let mut pretty = BannerDescription {
+        base: banner.base,
+        layers: Vec::with_capacity(banner.layers.len()),
+    };
+    let mut visible = 0;
+    // if the top layer keeps everything below visible, then how much it stands out
+    let rate = |pretty: &BannerDescription, visible: usize| -> anyhow::Result<(bool, f32)> {
+        if registry.canonicalize(pretty)?.layers.len() <= visible {
+            return Ok((false, 0.0));
+        }
+        Ok((
+            true,
+            analyze(registry, pretty)?.layers.last().unwrap().contrast,
+        ))
+    };
+    let good = |(shown, contrast): (bool, f32)| shown && contrast >= MIN_CONTRAST;
+
+    for (i, layer) in banner.layers.into_iter().enumerate() {
+        pretty.layers.push(layer);
+        if !fixed.get(i).copied().unwrap_or(false) {
+            // only what's beneath matters, so the layers above can wait
+            let mut best = (rate(&pretty, visible)?, layer);
+            for _ in 0..MAX_REDRAWS {
+                if good(best.0) {
+                    break;
+                }
+
+                let redraw = draw(rng);
+                *pretty.layers.last_mut().unwrap() = redraw;
+                let rating = rate(&pretty, visible)?;
+                if rating > best.0 {
+                    best = (rating, redraw);
+                }
+            }
+
+            if good(best.0) || !droppable(i) {
+                *pretty.layers.last_mut().unwrap() = best.1;
+            } else {
+                pretty.layers.pop();
+            }
+        }
+        visible = registry.canonicalize(&pretty)?.layers.len();
+    }
+
+    // overridden layers can still hide others
+    let visible = registry.visible_layers(&pretty)?;
+    pretty.layers = pretty
+        .layers
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !droppable(*i) || visible.contains(i))
+        .map(|(_, layer)| layer)
+        .collect();
+    Ok(pretty)
+}
@@ -0,0 +1,27 @@
+//! Fixtures for tests, only built for this crate's own tests & with the `test-support` feature.
+
+use image::Rgba;
+use mcb::Banner;
+
+use crate::{Image, PatternRegistry};
+
+/// Full size `64x64` textures with simple shapes on the banner front (`1..21, 1..41`),
+/// named `pattern_0`, `pattern_1` etc in the order of `shapes`
+pub fn shape_registry(shapes: &[fn(u32, u32) -> bool]) -> PatternRegistry {
+    let patterns = shapes
+        .iter()
+        .enumerate()
+        .map(|(i, shape)| {
+            let texture = Image::from_fn(64, 64, |x, y| {
+                let (x, y) = (x.wrapping_sub(1), y.wrapping_sub(1));
+                if x < Banner::X && y < Banner::Y && shape(x, y) {
+                    Rgba([255, 255, 255, 255])
+                } else {
+                    Rgba([0, 0, 0, 0])
+                }
+            });
+            (format!("pattern_{i}"), texture)
+        })
+        .collect();
+    PatternRegistry::new(patterns).unwrap()
+}
@@ -0,0 +1,196 @@
+//! Writing with banners, one banner per letter.
+//!
+//! Every recipe only uses vanilla patterns (by name) & fits in 6 layers, so they're all craftable in survival.
+//! A registry missing one of the patterns can't write the letters that need it.
+
+use mcb::Color;
+use thiserror::Error;
+
+use crate::{BannerDescription, PatternRegistry};
+
+/// Which of the two colors a layer is dyed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ink {
+    /// The letter itself
+    Foreground,
+    /// Same as the base, to cut parts of the letter back out
+    Background,
+}
+
+use Ink::{Background as B, Foreground as F};
+
+/// Every character & its layers, bottom to top on a base of the background color
+#[rustfmt::skip]
+const RECIPES: &[(char, &[(&str, Ink)])] = &[
+    ('A', &[("stripe_left", F), ("stripe_right", F), ("stripe_top", F), ("stripe_middle", F), ("border", B)]),
+    ('B', &[("stripe_right", F), ("stripe_top", F), ("stripe_bottom", F), ("stripe_middle", F), ("curly_border", B), ("stripe_left", F)]),
+    ('C', &[("stripe_top", F), ("stripe_bottom", F), ("stripe_left", F), ("border", B)]),
+    ('D', &[("stripe_right", F), ("stripe_bottom", F), ("stripe_top", F), ("curly_border", B), ("stripe_left", F), ("border", B)]),
+    ('E', &[("stripe_left", F), ("stripe_top", F), ("stripe_middle", F), ("stripe_bottom", F), ("border", B)]),
+    ('F', &[("stripe_middle", F), ("stripe_right", B), ("stripe_left", F), ("stripe_top", F), ("border", B)]),
+    ('G', &[("stripe_right", F), ("half_horizontal", B), ("stripe_bottom", F), ("stripe_left", F), ("stripe_top", F), ("border", B)]),
+    ('H', &[("stripe_left", F), ("stripe_right", F), ("stripe_middle", F), ("border", B)]),
+    ('I', &[("stripe_center", F), ("stripe_top", F), ("stripe_bottom", F), ("border", B)]),
+    ('J', &[("stripe_left", F), ("half_horizontal", B), ("stripe_bottom", F), ("stripe_right", F), ("border", B)]),
+    ('K', &[("stripe_downright", F), ("half_horizontal", B), ("stripe_downleft", F), ("stripe_left", F), ("border", B)]),
+    ('L', &[("stripe_bottom", F), ("stripe_left", F), ("border", B)]),
+    ('M', &[("triangle_top", F), ("triangles_top", B), ("stripe_left", F), ("stripe_right", F), ("border", B)]),
+    ('N', &[("stripe_left", F), ("triangle_top", B), ("stripe_downright", F), ("stripe_right", F), ("border", B)]),
+    ('O', &[("stripe_left", F), ("stripe_right", F), ("stripe_bottom", F), ("stripe_top", F), ("border", B)]),
+    ('P', &[("stripe_right", F), ("half_horizontal_bottom", B), ("stripe_middle", F), ("stripe_top", F), ("stripe_left", F), ("border", B)]),
+    ('Q', &[("stripe_left", F), ("stripe_right", F), ("stripe_top", F), ("square_bottom_right", F), ("stripe_bottom", F), ("border", B)]),
+    ('R', &[("half_horizontal", F), ("stripe_center", B), ("stripe_top", F), ("stripe_left", F), ("stripe_downright", F), ("border", B)]),
+    ('S', &[("stripe_top", F), ("stripe_bottom", F), ("stripe_downright", F), ("border", B)]),
+    ('T', &[("stripe_top", F), ("stripe_center", F), ("border", B)]),
+    ('U', &[("stripe_bottom", F), ("stripe_left", F), ("stripe_right", F), ("border", B)]),
+    ('V', &[("stripe_left", F), ("triangle_bottom", B), ("stripe_downleft", F), ("border", B)]),
+    ('W', &[("triangle_bottom", F), ("triangles_bottom", B), ("stripe_left", F), ("stripe_right", F), ("border", B)]),
+    ('X', &[("cross", F), ("border", B)]),
+    ('Y', &[("stripe_downright", F), ("half_horizontal_bottom", B), ("stripe_downleft", F), ("border", B)]),
+    ('Z', &[("stripe_top", F), ("stripe_downleft", F), ("stripe_bottom", F), ("border", B)]),
+    ('0', &[("stripe_left", F), ("stripe_bottom", F), ("stripe_right", F), ("stripe_top", F), ("stripe_downleft", F), ("border", B)]),
+    ('1', &[("stripe_center", F), ("square_top_left", F), ("stripe_bottom", F), ("border", B)]),
+    ('2', &[("stripe_top", F), ("rhombus", B), ("stripe_bottom", F), ("stripe_downleft", F), ("border", B)]),
+    ('3', &[("stripe_bottom", F), ("stripe_middle", F), ("stripe_top", F), ("curly_border", B), ("stripe_right", F), ("border", B)]),
+    ('4', &[("stripe_left", F), ("half_horizontal_bottom", B), ("stripe_right", F), ("stripe_middle", F), ("border", B)]),
+    ('5', &[("stripe_right", F), ("half_horizontal", B), ("stripe_bottom", F), ("stripe_middle", F), ("square_top_left", F), ("stripe_top", F)]),
+    ('6', &[("stripe_bottom", F), ("stripe_right", F), ("half_horizontal", B), ("stripe_middle", F), ("stripe_top", F), ("stripe_left", F)]),
+    ('7', &[("stripe_downleft", F), ("stripe_top", F), ("border", B)]),
+    ('8', &[("stripe_top", F), ("stripe_left", F), ("stripe_middle", F), ("stripe_bottom", F), ("stripe_right", F), ("border", B)]),
+    ('9', &[("stripe_left", F), ("half_horizontal_bottom", B), ("stripe_middle", F), ("stripe_top", F), ("stripe_right", F), ("stripe_bottom", F)]),
+    (' ', &[]),
+    ('.', &[("square_bottom_left", F)]),
+    ('-', &[("stripe_middle", F), ("border", B)]),
+    ('_', &[("stripe_bottom", F)]),
+    ('+', &[("straight_cross", F), ("stripe_top", B), ("stripe_bottom", B), ("border", B)]),
+    ('/', &[("stripe_downleft", F), ("border", B)]),
+    ('\\', &[("stripe_downright", F), ("border", B)]),
+    ('|', &[("stripe_center", F)]),
+];
+
+#[derive(Debug, Error)]
+pub enum TextError {
+    #[error("There's no banner for '{0}'")]
+    UnknownCharacter(char),
+    #[error("The '{0}' pattern isn't loaded")]
+    MissingPattern(&'static str),
+}
+
+/// Every character there's a recipe for, letters are upper case but lower case works too
+pub fn characters() -> impl Iterator<Item = char> {
+    RECIPES.iter().map(|(character, _)| *character)
+}
+
+/// The `(pattern name, ink)` layers for `character`, bottom to top
+pub fn recipe(character: char) -> Option<&'static [(&'static str, Ink)]> {
+    let character = character.to_ascii_uppercase();
+    RECIPES
+        .iter()
+        .find(|(c, _)| *c == character)
+        .map(|(_, layers)| *layers)
+}
+
+/// The banner for a single character
+pub fn letter(
+    registry: &PatternRegistry,
+    character: char,
+    foreground: Color,
+    background: Color,
+) -> Result<BannerDescription, TextError> {
+    let recipe = recipe(character).ok_or(TextError::UnknownCharacter(character))?;
+
+    let mut layers = Vec::with_capacity(recipe.len());
+    for (pattern, ink) in recipe {
+        let id = registry
+            .id(pattern)
+            .ok_or(TextError::MissingPattern(pattern))?;
+        let color = match ink {
+            Ink::Foreground => foreground,
+            Ink::Background => background,
+        };
+        layers.push((id, color));
+    }
+
+    Ok(BannerDescription {
+        base: background,
+        layers,
+    })
+}
+
+/// One banner per character of `text`, see [`PatternRegistry::render_row`] to render them
+pub fn banners(
+    registry: &PatternRegistry,
+    text: &str,
+    foreground: Color,
+    background: Color,
+) -> Result<Vec<BannerDescription>, TextError> {
+    text.chars()
+        .map(|c| letter(registry, c, foreground, background))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Image, blazon::CHARGES};
+    use mcb::Banner;
+    use std::collections::HashSet;
+
+    /// Blank textures named like every vanilla pattern
+    fn vanilla_registry() -> PatternRegistry {
+        let patterns = CHARGES
+            .iter()
+            .map(|(name, _)| (name.to_string(), Image::new(64, 64)))
+            .collect();
+        PatternRegistry::new(patterns).unwrap()
+    }
+
+    #[test]
+    fn recipes_are_craftable() {
+        for (character, layers) in RECIPES {
+            assert!(
+                layers.len() <= Banner::VANILLA_MAX_PATTERN_SIZE,
+                "'{character}' has too many layers"
+            );
+            for (pattern, _) in *layers {
+                assert!(
+                    CHARGES.iter().any(|(id, _)| id == pattern),
+                    "'{character}' uses '{pattern}', which isn't a vanilla pattern"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn writes_every_character() {
+        let registry = vanilla_registry();
+        let text = characters().collect::<String>();
+        let banners = banners(&registry, &text, Color::Black, Color::White).unwrap();
+
+        assert_eq!(banners.len(), RECIPES.len());
+        for (banner, (_, layers)) in banners.iter().zip(RECIPES) {
+            assert_eq!(banner.base, Color::White);
+            let names = banner
+                .layers
+                .iter()
+                .map(|(id, _)| registry.name(*id).unwrap())
+                .collect::<Vec<_>>();
+            let expected = layers.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+            assert_eq!(names, expected);
+        }
+        registry.render_row(&banners).unwrap();
+    }
+
+    #[test]
+    fn characters_are_unique() {
+        let unique = characters().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), RECIPES.len());
+        assert!(characters().all(|c| !c.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn case_doesnt_matter() {
+        assert_eq!(recipe('a'), recipe('A'));
+        assert!(recipe('?').is_none());
+    }
+}
@@ -0,0 +1,137 @@
+//! Pins the banner of fixed seeds for every [`GeneratorVersion`].
+//!
+//! These must never change, a failure here means links out there now show a different banner.
+//! Fixes go into a new version instead.
+
+use mcb::{Banner, Color};
+use minecraft_banners::{
+    BannerDescription, BannerGenerator, GenerateOptions, GeneratorVersion, Image, PatternRegistry,
+    VersionedSeed, parse_seed,
+};
+
+/// How many patterns the registry had when these were pinned, the amount changes every seed
+const PATTERNS: usize = 24;
+const SEEDS: [u128; 6] = [
+    0,
+    1,
+    42,
+    123_456_789,
+    987_654_321_987,
+    51_432_753_968_977_935,
+];
+
+fn registry() -> PatternRegistry {
+    let patterns = (0..PATTERNS)
+        .map(|i| (format!("pattern_{i:02}"), Image::new(Banner::X, Banner::Y)))
+        .collect();
+    PatternRegistry::new(patterns).unwrap()
+}
+
+/// `base: [pattern/color, ..]` with colors as their id
+fn describe(banner: &BannerDescription) -> String {
+    let layers = banner
+        .layers
+        .iter()
+        .map(|(pattern, color)| format!("{pattern}/{}", *color as u8))
+        .collect::<Vec<_>>();
+    format!("{}: [{}]", banner.base as u8, layers.join(", "))
+}
+
+fn generate(version: GeneratorVersion, options: impl Fn() -> GenerateOptions) -> Vec<String> {
+    let registry = registry();
+    let generator = BannerGenerator::with_version(&registry, version);
+    SEEDS
+        .iter()
+        .map(|seed| {
+            let (_, banner) = generator.generate(Some(*seed), options()).unwrap();
+            describe(&banner)
+        })
+        .collect()
+}
+
+/// Overrides go through the same rng, v1's `[pattern,]` takes the color of another layer
+fn overrides() -> GenerateOptions {
+    GenerateOptions {
+        base_color: Some(Color::Cyan),
+        layers: vec![
+            Some((Some(3), None)),
+            Some((None, Some(Color::Red))),
+            None,
+            Some((Some(1), Some(Color::White))),
+        ],
+        max_layers: Some(5),
+    }
+}
+
+#[test]
+fn v1() {
+    assert_eq!(
+        generate(GeneratorVersion::V1, GenerateOptions::default),
+        [
+            "13: [16/7, 11/11, 12/0, 4/14, 13/8, 9/13]",
+            "11: [22/1, 4/9, 8/3, 19/4, 21/11, 11/7]",
+            "2: [3/15, 17/6, 7/10, 16/4, 14/2, 7/4]",
+            "0: [1/10, 11/3, 6/2, 5/15, 9/15, 18/8]",
+            "14: [3/8, 8/1, 7/7, 15/0, 22/8, 15/1]",
+            "5: [7/15, 18/3, 15/13, 14/2, 21/7, 7/7]",
+        ]
+    );
+    assert_eq!(
+        generate(GeneratorVersion::V1, overrides),
+        [
+            "9: [3/14, 11/14, 12/0, 1/0, 13/8]",
+            "9: [3/4, 4/14, 8/3, 1/0, 21/11]",
+            "9: [3/4, 17/14, 7/10, 1/0, 14/2]",
+            "9: [3/15, 11/14, 6/2, 1/0, 9/15]",
+            "9: [3/0, 8/14, 7/7, 1/0, 22/8]",
+            "9: [3/2, 18/14, 15/13, 1/0, 21/7]",
+        ]
+    );
+}
+
+#[test]
+fn v2() {
+    assert_eq!(
+        generate(GeneratorVersion::V2, GenerateOptions::default),
+        [
+            "13: [17/7, 12/11, 13/0, 4/14, 13/8, 9/13]",
+            "11: [23/1, 5/9, 9/3, 19/4, 21/11, 11/7]",
+            "2: [3/15, 18/6, 8/10, 17/4, 15/2, 7/4]",
+            "0: [2/10, 12/3, 6/2, 5/15, 10/15, 19/8]",
+            "14: [3/8, 8/1, 8/7, 15/0, 23/8, 16/1]",
+            "5: [8/15, 19/3, 15/13, 15/2, 22/7, 7/7]",
+        ]
+    );
+    assert_eq!(
+        generate(GeneratorVersion::V2, overrides),
+        [
+            "9: [3/7, 12/14, 13/0, 1/0, 13/8]",
+            "9: [3/1, 5/14, 9/3, 1/0, 21/11]",
+            "9: [3/15, 18/14, 8/10, 1/0, 15/2]",
+            "9: [3/10, 12/14, 6/2, 1/0, 10/15]",
+            "9: [3/8, 8/14, 8/7, 1/0, 23/8]",
+            "9: [3/15, 19/14, 15/13, 1/0, 22/7]",
+        ]
+    );
+}
+
+#[test]
+fn every_version_is_pinned() {
+    // add a test above when adding a version
+    assert_eq!(GeneratorVersion::ALL.len(), 2);
+}
+
+#[test]
+fn versioned_seeds_round_trip() {
+    for version in GeneratorVersion::ALL {
+        let seed = VersionedSeed::pinned(version, 42);
+        assert_eq!(parse_seed(&seed.to_string()), Some(seed));
+    }
+    assert_eq!(
+        parse_seed("V2-42"),
+        Some(VersionedSeed::pinned(GeneratorVersion::V2, 42))
+    );
+    assert_eq!(parse_seed("42").unwrap().version, None);
+    assert_eq!(parse_seed("v9-42"), None);
+    assert_eq!(parse_seed("v2-"), None);
+}
@@ -0,0 +1,322 @@
+//! Statistical checks that seeds cover every banner evenly.
+//!
+//! Seeds are `0..SAMPLES` so these are deterministic, the bounds are chi-squared critical values
+//! at `p = 0.001`, an unbiased generator fails one about once in a thousand different seed ranges.
+
+use mcb::{Banner, Color};
+use minecraft_banners::{
+    BannerGenerator, Combinations, Constraints, GenerateOptions, GenerationError, GeneratorVersion,
+    Image, PatternRegistry, Profile,
+};
+
+const PATTERNS: usize = 5;
+const COLORS: usize = Color::all().len();
+const SAMPLES: u128 = 100_000;
+
+fn registry() -> PatternRegistry {
+    let patterns = (0..PATTERNS)
+        .map(|i| (format!("pattern_{i}"), Image::new(Banner::X, Banner::Y)))
+        .collect();
+    PatternRegistry::new(patterns).unwrap()
+}
+
+struct Counts {
+    patterns: Vec<f64>,
+    layer_colors: Vec<f64>,
+    base_colors: Vec<f64>,
+    layer_amounts: Vec<f64>,
+}
+
+fn sample(version: GeneratorVersion) -> Counts {
+    let registry = registry();
+    let generator = BannerGenerator::with_version(&registry, version);
+
+    let mut counts = Counts {
+        patterns: vec![0.0; PATTERNS],
+        layer_colors: vec![0.0; COLORS],
+        base_colors: vec![0.0; COLORS],
+        layer_amounts: vec![0.0; Banner::VANILLA_MAX_PATTERN_SIZE + 1],
+    };
+    for seed in 0..SAMPLES {
+        let (_, banner) = generator
+            .generate(Some(seed), GenerateOptions::default())
+            .unwrap();
+
+        counts.base_colors[banner.base as usize] += 1.0;
+        counts.layer_amounts[banner.layers.len()] += 1.0;
+        for (pattern, color) in banner.layers {
+            counts.patterns[pattern] += 1.0;
+            counts.layer_colors[color as usize] += 1.0;
+        }
+    }
+    counts
+}
+
+fn chi_squared(observed: &[f64], expected: &[f64]) -> f64 {
+    observed
+        .iter()
+        .zip(expected)
+        .map(|(o, e)| (o - e).powi(2) / e)
+        .sum()
+}
+
+fn uniform(observed: &[f64]) -> Vec<f64> {
+    let total: f64 = observed.iter().sum();
+    vec![total / observed.len() as f64; observed.len()]
+}
+
+#[test]
+fn every_pattern_and_color_is_uniform() {
+    let counts = sample(GeneratorVersion::V2);
+
+    assert!(
+        counts.patterns.iter().all(|c| *c > 0.0),
+        "{:?}",
+        counts.patterns
+    );
+
+    // 4 & 15 degrees of freedom
+    let patterns = chi_squared(&counts.patterns, &uniform(&counts.patterns));
+    assert!(
+        patterns < 18.47,
+        "patterns: {patterns} {:?}",
+        counts.patterns
+    );
+
+    let layer_colors = chi_squared(&counts.layer_colors, &uniform(&counts.layer_colors));
+    assert!(layer_colors < 37.70, "layer colors: {layer_colors}");
+
+    let base_colors = chi_squared(&counts.base_colors, &uniform(&counts.base_colors));
+    assert!(base_colors < 37.70, "base colors: {base_colors}");
+}
+
+#[test]
+fn layer_amounts_follow_combinations() {
+    let counts = sample(GeneratorVersion::V2);
+    let combinations = Combinations::vanilla(PATTERNS).unwrap();
+
+    let expected = combinations
+        .per_layer
+        .iter()
+        .map(|c| *c as f64 / combinations.total as f64 * SAMPLES as f64)
+        .collect::<Vec<_>>();
+
+    // everything under 5 layers is too rare for its own bin
+    let merge = |counts: &[f64]| vec![counts[..5].iter().sum(), counts[5], counts[6]];
+    let amounts = chi_squared(&merge(&counts.layer_amounts), &merge(&expected));
+    // 2 degrees of freedom
+    assert!(
+        amounts < 13.82,
+        "layer amounts: {amounts} {:?}",
+        counts.layer_amounts
+    );
+}
+
+#[test]
+fn v1_never_picks_the_last_pattern() {
+    let counts = sample(GeneratorVersion::V1);
+
+    assert_eq!(counts.patterns[PATTERNS - 1], 0.0);
+    assert!(counts.patterns[..PATTERNS - 1].iter().all(|c| *c > 0.0));
+}
+
+#[test]
+fn seeds_stop_before_the_total() {
+    let registry = registry();
+    let total = Combinations::vanilla(PATTERNS).unwrap().total;
+
+    for version in GeneratorVersion::ALL {
+        let generator = BannerGenerator::with_version(&registry, version);
+        assert!(
+            generator
+                .generate(Some(total - 1), GenerateOptions::default())
+                .is_ok()
+        );
+        assert!(matches!(
+            generator.generate(Some(total), GenerateOptions::default()),
+            Err(GenerationError::SeedOutOfRange { .. })
+        ));
+    }
+}
+
+#[test]
+fn constraints_are_uniform_within() {
+    let registry = registry();
+    let constraints = Constraints {
+        colors: Some(vec![Color::Red, Color::White, Color::Black]),
+        patterns: Some(vec![4, 0, 2]),
+        exclude_patterns: vec![4],
+    };
+    let generator =
+        BannerGenerator::with_version(&registry, GeneratorVersion::V2).constrained(&constraints);
+    let total = generator.combinations().unwrap().total;
+    assert_eq!(total, Combinations::with_colors(2, 3, 6).unwrap().total);
+
+    let mut patterns = [0.0; PATTERNS];
+    let mut colors = [0.0; COLORS];
+    for seed in 0..SAMPLES {
+        let (_, banner) = generator
+            .generate(Some(seed % total), GenerateOptions::default())
+            .unwrap();
+        colors[banner.base as usize] += 1.0;
+        for (pattern, color) in banner.layers {
+            patterns[pattern] += 1.0;
+            colors[color as usize] += 1.0;
+        }
+    }
+
+    assert_eq!(patterns[1] + patterns[3] + patterns[4], 0.0);
+    let allowed = [Color::Red, Color::White, Color::Black].map(|c| colors[c as usize]);
+    assert_eq!(allowed.iter().sum::<f64>(), colors.iter().sum::<f64>());
+
+    // 1 & 2 degrees of freedom
+    let patterns = [patterns[0], patterns[2]];
+    assert!(chi_squared(&patterns, &uniform(&patterns)) < 10.83);
+    assert!(chi_squared(&allowed, &uniform(&allowed)) < 13.82);
+}
+
+#[test]
+fn no_constraints_keep_every_seed() {
+    let registry = registry();
+    let constraints = Constraints::default();
+    let plain = BannerGenerator::with_version(&registry, GeneratorVersion::V2);
+    let constrained = plain.constrained(&constraints);
+
+    assert_eq!(
+        plain.combinations().unwrap(),
+        constrained.combinations().unwrap()
+    );
+    for seed in 0..1000 {
+        assert_eq!(
+            plain
+                .generate(Some(seed), GenerateOptions::default())
+                .unwrap(),
+            constrained
+                .generate(Some(seed), GenerateOptions::default())
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn invalid_constraints_error() {
+    let registry = registry();
+    let v2 = BannerGenerator::with_version(&registry, GeneratorVersion::V2);
+
+    let no_colors = Constraints {
+        colors: Some(vec![]),
+        ..Default::default()
+    };
+    assert!(matches!(
+        v2.constrained(&no_colors).random_seed(),
+        Err(GenerationError::NoColors)
+    ));
+
+    let unknown = Constraints {
+        patterns: Some(vec![PATTERNS]),
+        ..Default::default()
+    };
+    assert!(matches!(
+        v2.constrained(&unknown).random_seed(),
+        Err(GenerationError::UnknownPattern { .. })
+    ));
+
+    let red = Constraints {
+        colors: Some(vec![Color::Red]),
+        ..Default::default()
+    };
+    let v1 = BannerGenerator::with_version(&registry, GeneratorVersion::V1);
+    assert!(matches!(
+        v1.constrained(&red).random_seed(),
+        Err(GenerationError::UnsupportedConstraints { .. })
+    ));
+}
+
+#[test]
+fn profiles_follow_their_weights() {
+    let registry = registry();
+    let profile = Profile {
+        patterns: vec![(0, 0.0), (1, 3.0)],
+        colors: vec![(Color::White, 15.0)],
+        layers: vec![0.0, 1.0, 1.0],
+    };
+    let generator =
+        BannerGenerator::with_version(&registry, GeneratorVersion::V2).weighted(Some(&profile));
+
+    let mut patterns = [0.0; PATTERNS];
+    let mut colors = [0.0; COLORS];
+    let mut amounts = [0.0; 2];
+    for seed in 0..SAMPLES {
+        let (_, banner) = generator
+            .generate(Some(seed), GenerateOptions::default())
+            .unwrap();
+        amounts[banner.layers.len() - 1] += 1.0;
+        colors[banner.base as usize] += 1.0;
+        for (pattern, color) in banner.layers {
+            patterns[pattern] += 1.0;
+            colors[color as usize] += 1.0;
+        }
+    }
+
+    assert_eq!(patterns[0], 0.0);
+    // pattern 1 weighs 3, the others 1. White weighs as much as the other 15 colors together
+    let total: f64 = patterns.iter().sum();
+    let expected = [0.0, 3.0, 1.0, 1.0, 1.0].map(|w| w / 6.0 * total);
+    let colors_total: f64 = colors.iter().sum();
+    let expected_colors = Color::all()
+        .iter()
+        .map(|c| if *c == Color::White { 0.5 } else { 0.5 / 15.0 } * colors_total)
+        .collect::<Vec<_>>();
+
+    // 3, 15 & 1 degrees of freedom
+    assert!(chi_squared(&patterns[1..], &expected[1..]) < 16.27);
+    assert!(chi_squared(&colors, &expected_colors) < 37.70);
+    assert!(chi_squared(&amounts, &uniform(&amounts)) < 10.83);
+}
+
+#[test]
+fn profiles_change_seeds_reproducibly() {
+    let registry = registry();
+    let profile = Profile {
+        patterns: vec![(2, 10.0)],
+        ..Default::default()
+    };
+    let plain = BannerGenerator::with_version(&registry, GeneratorVersion::V2);
+    let weighted = plain.weighted(Some(&profile));
+
+    // seeds keep their range
+    assert_eq!(
+        plain.combinations().unwrap(),
+        weighted.combinations().unwrap()
+    );
+    let generate = |generator: BannerGenerator, seed| {
+        generator
+            .generate(Some(seed), GenerateOptions::default())
+            .unwrap()
+            .1
+    };
+    let changed = (0..1000)
+        .filter(|seed| {
+            assert_eq!(generate(weighted, *seed), generate(weighted, *seed));
+            generate(plain, *seed) != generate(weighted, *seed)
+        })
+        .count();
+    assert!(changed > 500);
+
+    let v1 = BannerGenerator::with_version(&registry, GeneratorVersion::V1);
+    assert!(matches!(
+        v1.weighted(Some(&profile)).random_seed(),
+        Err(GenerationError::UnsupportedProfile { .. })
+    ));
+
+    let nothing = Profile {
+        colors: Color::all().iter().map(|c| (*c, 0.0)).collect(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        plain
+            .weighted(Some(&nothing))
+            .generate(Some(1), GenerateOptions::default()),
+        Err(GenerationError::InvalidWeights { what: "color" })
+    ));
+}
@@ -0,0 +1,64 @@
+//! [`Features`] distances & the neighbors [`similar`] finds for a seed.
+
+use mcb::Color;
+use minecraft_banners::{
+    BannerDescription, BannerGenerator, Features, Neighbor, similar, test_support::shape_registry,
+};
+
+/// A left & a top stripe, a diagonal & a horizontal band
+const SHAPES: &[fn(u32, u32) -> bool] = &[
+    |x, _| x < 10,
+    |_, y| y < 20,
+    |x, y| x * 2 > y,
+    |_, y| (15..25).contains(&y),
+];
+
+#[test]
+fn distance_is_a_distance() {
+    let registry = shape_registry(SHAPES);
+    let a = BannerDescription {
+        base: Color::White,
+        layers: vec![(0, Color::Red), (1, Color::Blue)],
+    };
+    let b = BannerDescription {
+        base: Color::White,
+        layers: vec![(0, Color::Red), (1, Color::Cyan)],
+    };
+    let c = BannerDescription {
+        base: Color::Black,
+        layers: vec![(2, Color::Yellow)],
+    };
+    let (a, b, c) = (
+        Features::new(&registry, &a).unwrap(),
+        Features::new(&registry, &b).unwrap(),
+        Features::new(&registry, &c).unwrap(),
+    );
+
+    assert_eq!(a.distance(&a), 0.0);
+    assert_eq!(a.distance(&b), b.distance(&a));
+    assert!(a.distance(&b) < a.distance(&c));
+    assert!(a.distance(&c) <= 1.0);
+}
+
+#[test]
+fn neighbors_are_reproducible() {
+    let registry = shape_registry(SHAPES);
+    let generator = BannerGenerator::new(&registry);
+    let (_, target) = generator.generate(Some(1234), Default::default()).unwrap();
+
+    let neighbors = similar(&generator, 1234, 20).unwrap();
+    assert_eq!(neighbors.len(), 20);
+    assert!(neighbors.is_sorted_by(|a, b| a.distance <= b.distance));
+
+    for neighbor in &neighbors {
+        let (_, banner) = generator
+            .generate(Some(neighbor.seed), neighbor.options.clone())
+            .unwrap();
+        assert_eq!(banner, neighbor.banner);
+        assert!(!registry.identical(&banner, &target).unwrap());
+    }
+
+    let again = similar(&generator, 1234, 20).unwrap();
+    let banners = |n: &[Neighbor]| n.iter().map(|n| n.banner.clone()).collect::<Vec<_>>();
+    assert_eq!(banners(&neighbors), banners(&again));
+}
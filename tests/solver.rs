@@ -0,0 +1,62 @@
+//! [`Solver`] against renders of known banners: exact matches, the `max_layers` limit & input sizes.
+
+use image::Rgba;
+use mcb::Color;
+use minecraft_banners::{
+    BannerDescription, Image, SolveOptions, Solver, test_support::shape_registry,
+};
+
+/// A left & a top stripe, a diagonal, a horizontal band & a thick frame
+const SHAPES: &[fn(u32, u32) -> bool] = &[
+    |x, _| x < 10,
+    |_, y| y < 20,
+    |x, y| x * 2 > y,
+    |_, y| (15..25).contains(&y),
+    |x, y| !(3..17).contains(&x) || !(3..37).contains(&y),
+];
+
+#[test]
+fn finds_banners_it_can_make() {
+    let registry = shape_registry(SHAPES);
+    let solver = Solver::new(&registry).unwrap();
+
+    let banner = BannerDescription {
+        base: Color::Blue,
+        layers: vec![(1, Color::Red), (0, Color::Yellow), (4, Color::Black)],
+    };
+    let target = registry.render(&banner).unwrap();
+
+    let solution = solver.solve(&target, SolveOptions::default());
+    assert_eq!(solution.error, 0.0, "{solution:?}");
+    assert_eq!(registry.render(&solution.banner).unwrap(), target);
+}
+
+#[test]
+fn stays_within_max_layers() {
+    let registry = shape_registry(SHAPES);
+    let solver = Solver::new(&registry).unwrap();
+
+    let banner = BannerDescription {
+        base: Color::White,
+        layers: vec![(2, Color::Green), (3, Color::Purple), (0, Color::Orange)],
+    };
+    let target = registry.render(&banner).unwrap();
+
+    let options = SolveOptions {
+        max_layers: 1,
+        beam_width: 1,
+    };
+    let solution = solver.solve(&target, options);
+    assert!(solution.banner.layers.len() <= 1);
+    assert!(solution.error > 0.0);
+}
+
+#[test]
+fn stretches_other_sizes() {
+    let registry = shape_registry(SHAPES);
+    let solver = Solver::new(&registry).unwrap();
+
+    let target = Image::from_pixel(200, 300, Rgba([0xB0, 0x2E, 0x26, 255]));
+    let solution = solver.solve(&target, SolveOptions::default());
+    assert_eq!(solution.banner.base, Color::Red, "{solution:?}");
+}
@@ -0,0 +1,113 @@
+//! [`score`] & `style=pretty` generation: scores, reproducible seeds & overridden layers.
+
+use mcb::Color;
+use minecraft_banners::{
+    BannerDescription, BannerGenerator, GenerateOptions, Style, score, test_support::shape_registry,
+};
+
+const SAMPLES: u32 = 100;
+
+/// A left & a top stripe, a full cover & a horizontal band
+const SHAPES: &[fn(u32, u32) -> bool] = &[
+    |x, _| x < 10,
+    |_, y| y < 20,
+    |_, _| true,
+    |_, y| (15..25).contains(&y),
+];
+
+#[test]
+fn score_counts_blending_and_hidden_layers() {
+    let registry = shape_registry(SHAPES);
+    let banner = |layers| BannerDescription {
+        base: Color::White,
+        layers,
+    };
+
+    assert_eq!(score(&registry, &banner(vec![])).unwrap(), 1.0);
+    assert_eq!(
+        score(&registry, &banner(vec![(0, Color::Black)])).unwrap(),
+        1.0
+    );
+    // white on white
+    assert_eq!(
+        score(&registry, &banner(vec![(0, Color::White)])).unwrap(),
+        0.0
+    );
+    // the first layer is hidden by the full cover
+    assert_eq!(
+        score(&registry, &banner(vec![(0, Color::Black), (2, Color::Red)])).unwrap(),
+        0.5
+    );
+}
+
+#[test]
+fn pretty_banners_score_better() {
+    let registry = shape_registry(SHAPES);
+    let random = BannerGenerator::new(&registry);
+    let pretty = random.styled(Style::Pretty);
+
+    let mut random_total = 0.0;
+    for seed in 0..SAMPLES as u128 {
+        let (_, banner) = random.generate(Some(seed), Default::default()).unwrap();
+        random_total += score(&registry, &banner).unwrap();
+
+        let (_, banner) = pretty.generate(Some(seed), Default::default()).unwrap();
+        assert_eq!(score(&registry, &banner).unwrap(), 1.0, "{banner:?}");
+    }
+
+    assert!(random_total < SAMPLES as f32);
+}
+
+#[test]
+fn pretty_banners_are_reproducible() {
+    let registry = shape_registry(SHAPES);
+    let generator = BannerGenerator::new(&registry).styled(Style::Pretty);
+
+    let mut checked = 0;
+    for seed in 0..SAMPLES as u128 {
+        let options = GenerateOptions {
+            layers: vec![Some((Some(0), Some(Color::White)))],
+            ..Default::default()
+        };
+        let Ok((_, first)) = generator.generate(Some(seed), options.clone()) else {
+            // seeds without layers can't be overridden
+            continue;
+        };
+        let (_, again) = generator.generate(Some(seed), options).unwrap();
+        assert_eq!(first, again);
+        // overrides are never redrawn
+        assert_eq!(first.layers[0], (0, Color::White));
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn overrides_stay_where_they_are() {
+    let registry = shape_registry(SHAPES);
+    let generator = BannerGenerator::new(&registry).styled(Style::Pretty);
+    // pattern 2 covers the whole banner, so the first override is completely hidden
+    let overrides = [(2, Color::Red), (2, Color::Red), (0, Color::Red)];
+    let options = GenerateOptions {
+        layers: vec![
+            None,
+            Some((Some(overrides[0].0), Some(overrides[0].1))),
+            None,
+            Some((Some(overrides[1].0), Some(overrides[1].1))),
+            Some((Some(overrides[2].0), Some(overrides[2].1))),
+        ],
+        ..Default::default()
+    };
+
+    let mut checked = 0;
+    for seed in 0..20 {
+        let Ok((_, banner)) = generator.generate(Some(seed), options.clone()) else {
+            continue;
+        };
+        assert_eq!(banner.layers[1], overrides[0]);
+        assert_eq!(banner.layers[3], overrides[1]);
+        assert_eq!(banner.layers[4], overrides[2]);
+        checked += 1;
+    }
+    assert!(checked > 0);
+}